@@ -1,6 +1,9 @@
 use super::*;
 
 use anyhow::Result;
+use anyhow::anyhow;
+
+use std::ops::Bound;
 
 impl Brighten {
 	pub fn value(&self) -> Result<u32> {
@@ -13,3 +16,134 @@ impl Contrast {
 		self.contrast.float()
 	}
 }
+
+impl HueShift {
+	pub fn degrees(&self) -> Result<f32> {
+		self.degrees.float()
+	}
+}
+
+impl Saturation {
+	pub fn factor(&self) -> Result<f32> {
+		self.factor.float()
+	}
+}
+
+impl Gamma {
+	pub fn validate(&self) -> Result<()> {
+		if !self.gamma.float_within((Bound::Excluded(0.0), Bound::Unbounded))? {
+			return Err(anyhow!("{}: gamma must be positive", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn gamma(&self) -> Result<f32> {
+		self.gamma.float()
+	}
+}
+
+impl WhiteBalance {
+	pub fn validate(&self) -> Result<()> {
+		// Range of the black body fit used for the temperature
+		if let Some(temperature) = self.temperature.as_ref() {
+			if !temperature.float_within(1000.0..=40000.0)? {
+				return Err(anyhow!("{}: temperature must be between 1000 and 40000", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn temperature(&self) -> Result<Option<f32>> {
+		self.temperature.as_ref().map(|t| t.float()).transpose()
+	}
+	pub fn gains(&self) -> Result<[f32; 3]> {
+		let mut gains = [1.0; 3];
+
+		for (gain, parameter) in gains.iter_mut().zip([&self.red, &self.green, &self.blue]) {
+			if let Some(parameter) = parameter {
+				*gain = parameter.float()?;
+			}
+		}
+
+		Ok(gains)
+	}
+}
+
+impl ColorCast {
+	pub fn strength(&self) -> Result<f32> {
+		self.strength.float()
+	}
+	pub fn hue(&self) -> Result<Option<f32>> {
+		self.hue.as_ref().map(|h| h.float()).transpose()
+	}
+}
+
+impl LowLight {
+	pub fn validate(&self) -> Result<()> {
+		let positive = (Bound::Excluded(0.0), Bound::Unbounded);
+
+		if !self.exposure.float_within(positive)? {
+			return Err(anyhow!("{}: exposure must be positive", self.id));
+		}
+
+		if let Some(gamma) = self.gamma.as_ref() {
+			if !gamma.float_within(positive)? {
+				return Err(anyhow!("{}: gamma must be positive", self.id));
+			}
+		}
+
+		for noise in [&self.shot_noise, &self.read_noise].into_iter().flatten() {
+			if !noise.float_within(0.0..)? {
+				return Err(anyhow!("{}: noise can not be negative", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn exposure(&self) -> Result<f32> {
+		self.exposure.float()
+	}
+	pub fn gamma(&self) -> Result<f32> {
+		match self.gamma.as_ref() {
+			Some(gamma) => gamma.float(),
+			None => Ok(1.0),
+		}
+	}
+	pub fn shot_noise(&self) -> Result<f32> {
+		match self.shot_noise.as_ref() {
+			Some(noise) => noise.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn read_noise(&self) -> Result<f32> {
+		match self.read_noise.as_ref() {
+			Some(noise) => noise.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl Overexposure {
+	pub fn validate(&self) -> Result<()> {
+		if !self.exposure.float_within((Bound::Excluded(0.0), Bound::Unbounded))? {
+			return Err(anyhow!("{}: exposure must be positive", self.id));
+		}
+
+		if let Some(clip) = self.clip.as_ref() {
+			if !clip.float_within((Bound::Excluded(0.0), Bound::Included(1.0)))? {
+				return Err(anyhow!("{}: clip must be above 0 and at most 1", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn exposure(&self) -> Result<f32> {
+		self.exposure.float()
+	}
+	pub fn clip(&self) -> Result<f32> {
+		match self.clip.as_ref() {
+			Some(clip) => clip.float(),
+			None => Ok(1.0),
+		}
+	}
+}
@@ -17,3 +17,60 @@ pub struct Contrast {
 	pub id: String,
 	contrast: Parameter,
 }
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct HueShift {
+	pub id: String,
+	degrees: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Saturation {
+	pub id: String,
+	factor: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Gamma {
+	pub id: String,
+	gamma: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct WhiteBalance {
+	pub id: String,
+	temperature: Option<Parameter>,
+	red: Option<Parameter>,
+	green: Option<Parameter>,
+	blue: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct ColorCast {
+	pub id: String,
+	strength: Parameter,
+	hue: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct LowLight {
+	pub id: String,
+	exposure: Parameter,
+	gamma: Option<Parameter>,
+	shot_noise: Option<Parameter>,
+	read_noise: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Overexposure {
+	pub id: String,
+	exposure: Parameter,
+	clip: Option<Parameter>,
+}
@@ -0,0 +1,105 @@
+use super::*;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+impl LineJitter {
+	pub fn validate(&self) -> Result<()> {
+		if !self.amplitude.float_within(0.0..)? {
+			return Err(anyhow!("{}: amplitude can not be negative", self.id));
+		}
+
+		// Offsets of fully correlated lines would never change
+		if let Some(correlation) = self.correlation.as_ref() {
+			if !correlation.float_within(0.0..1.0)? {
+				return Err(anyhow!("{}: correlation must be at least 0 and below 1", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn amplitude(&self) -> Result<f32> {
+		self.amplitude.float()
+	}
+	pub fn correlation(&self) -> Result<f32> {
+		match self.correlation.as_ref() {
+			Some(correlation) => correlation.float(),
+			None => Ok(0.8),
+		}
+	}
+}
+
+impl ChromaBleed {
+	pub fn validate(&self) -> Result<()> {
+		if !self.bleed.float_within(0.0..)? {
+			return Err(anyhow!("{}: bleed can not be negative", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn bleed(&self) -> Result<f32> {
+		self.bleed.float()
+	}
+	pub fn delay(&self) -> Result<f32> {
+		match self.delay.as_ref() {
+			Some(delay) => delay.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl TapeNoise {
+	pub fn validate(&self) -> Result<()> {
+		if let Some(height) = self.height.as_ref() {
+			if !height.int_within(1..)? {
+				return Err(anyhow!("{}: height must be at least 1", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn bands(&self) -> Result<u32> {
+		self.bands.int()
+	}
+	pub fn height(&self) -> Result<u32> {
+		match self.height.as_ref() {
+			Some(height) => height.int(),
+			None => Ok(4),
+		}
+	}
+	pub fn intensity(&self) -> Result<f32> {
+		self.intensity.float()
+	}
+}
+
+impl Interlace {
+	pub fn motion(&self) -> Result<f32> {
+		self.motion.float()
+	}
+}
+
+impl Scanlines {
+	pub fn validate(&self) -> Result<()> {
+		if !self.strength.float_within(0.0..=1.0)? {
+			return Err(anyhow!("{}: strength must be between 0 and 1", self.id));
+		}
+
+		// A period of 1 would darken every line by the same amount
+		if let Some(period) = self.period.as_ref() {
+			if !period.int_within(2..)? {
+				return Err(anyhow!("{}: period must be at least 2", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn strength(&self) -> Result<f32> {
+		self.strength.float()
+	}
+	pub fn period(&self) -> Result<u32> {
+		match self.period.as_ref() {
+			Some(period) => period.int(),
+			None => Ok(2),
+		}
+	}
+}
@@ -0,0 +1,45 @@
+use serde::Serialize;
+use serde::Deserialize;
+use super::enumerations::*;
+
+mod interface;
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct LineJitter {
+	pub id: String,
+	amplitude: Parameter,
+	correlation: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct ChromaBleed {
+	pub id: String,
+	bleed: Parameter,
+	delay: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct TapeNoise {
+	pub id: String,
+	bands: Parameter,
+	height: Option<Parameter>,
+	intensity: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Interlace {
+	pub id: String,
+	motion: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Scanlines {
+	pub id: String,
+	strength: Parameter,
+	period: Option<Parameter>,
+}
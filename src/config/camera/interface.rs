@@ -0,0 +1,161 @@
+use super::*;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+use std::ops::Bound;
+
+impl Mosaic {
+	pub fn pattern(&self) -> Result<CfaPattern> {
+		self.pattern.get()
+	}
+	pub fn demosaic(&self) -> Result<Demosaic> {
+		self.demosaic.get()
+	}
+	pub fn noise(&self) -> Result<f32> {
+		match self.noise.as_ref() {
+			Some(noise) => noise.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl CameraIsp {
+	pub fn pattern(&self) -> Result<CfaPattern> {
+		match self.pattern.as_ref() {
+			Some(pattern) => pattern.get(),
+			None => Choice::Rand.get(),
+		}
+	}
+	pub fn demosaic(&self) -> Result<Demosaic> {
+		match self.demosaic.as_ref() {
+			Some(demosaic) => demosaic.get(),
+			None => Ok(Demosaic::Bilinear),
+		}
+	}
+	pub fn rgb_gain(&self) -> Result<Option<f32>> {
+		self.rgb_gain.as_ref().map(|g| g.float()).transpose()
+	}
+	pub fn red_gain(&self) -> Result<Option<f32>> {
+		self.red_gain.as_ref().map(|g| g.float()).transpose()
+	}
+	pub fn blue_gain(&self) -> Result<Option<f32>> {
+		self.blue_gain.as_ref().map(|g| g.float()).transpose()
+	}
+	pub fn shot_noise(&self) -> Result<Option<f32>> {
+		self.shot_noise.as_ref().map(|n| n.float()).transpose()
+	}
+	pub fn read_noise(&self) -> Result<Option<f32>> {
+		self.read_noise.as_ref().map(|n| n.float()).transpose()
+	}
+}
+
+impl ChromaticAberration {
+	pub fn validate(&self) -> Result<()> {
+		if let Some(jitter) = self.jitter.as_ref() {
+			if !jitter.float_within(0.0..=1.0)? {
+				return Err(anyhow!("{}: jitter must be between 0 and 1", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn shift(&self) -> Result<f32> {
+		self.shift.float()
+	}
+	pub fn jitter(&self) -> Result<f32> {
+		match self.jitter.as_ref() {
+			Some(jitter) => jitter.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl Vignette {
+	pub fn validate(&self) -> Result<()> {
+		if !self.strength.float_within(0.0..=1.0)? {
+			return Err(anyhow!("{}: strength must be between 0 and 1", self.id));
+		}
+
+		// The falloff divides by the distance between the radius and the corners
+		if let Some(radius) = self.radius.as_ref() {
+			if !radius.float_within(0.0..1.0)? {
+				return Err(anyhow!("{}: radius must be at least 0 and below 1", self.id));
+			}
+		}
+
+		if let Some(shape) = self.shape.as_ref() {
+			if !shape.float_within((Bound::Excluded(0.0), Bound::Unbounded))? {
+				return Err(anyhow!("{}: shape must be positive", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn strength(&self) -> Result<f32> {
+		self.strength.float()
+	}
+	pub fn radius(&self) -> Result<f32> {
+		match self.radius.as_ref() {
+			Some(radius) => radius.float(),
+			None => Ok(0.5),
+		}
+	}
+	pub fn shape(&self) -> Result<f32> {
+		match self.shape.as_ref() {
+			Some(shape) => shape.float(),
+			None => Ok(2.0),
+		}
+	}
+}
+
+impl FilmGrain {
+	pub fn validate(&self) -> Result<()> {
+		if !self.intensity.float_within(0.0..)? {
+			return Err(anyhow!("{}: intensity can not be negative", self.id));
+		}
+
+		if let Some(size) = self.size.as_ref() {
+			if !size.float_within(0.0..)? {
+				return Err(anyhow!("{}: size can not be negative", self.id));
+			}
+		}
+
+		let response = self.response()?;
+
+		if response.iter().any(|(luma, scale)| !luma.is_finite() || !scale.is_finite()) {
+			return Err(anyhow!("{}: response points must be finite", self.id));
+		}
+
+		if response.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+			return Err(anyhow!("{}: response has several points at the same luminance", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn intensity(&self) -> Result<f32> {
+		self.intensity.float()
+	}
+	pub fn size(&self) -> Result<f32> {
+		match self.size.as_ref() {
+			Some(size) => size.float(),
+			None => Ok(1.0),
+		}
+	}
+	pub fn monochrome(&self) -> Result<bool> {
+		match self.monochrome.as_ref() {
+			Some(monochrome) => monochrome.get(),
+			None => Ok(true),
+		}
+	}
+	pub fn response(&self) -> Result<Vec<(f32, f32)>> {
+		let mut response = match self.response.as_ref() {
+			Some(response) => response.clone(),
+			None => vec![(0.0, 0.5), (0.5, 1.0), (1.0, 0.3)],
+		};
+
+		response.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+		Ok(response)
+	}
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+use serde::Deserialize;
+use super::enumerations::*;
+
+mod interface;
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Mosaic {
+	pub id: String,
+	pattern: Choice<CfaPattern>,
+	demosaic: Choice<Demosaic>,
+	noise: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct CameraIsp {
+	pub id: String,
+	pattern: Option<Choice<CfaPattern>>,
+	demosaic: Option<Choice<Demosaic>>,
+	rgb_gain: Option<Parameter>,
+	red_gain: Option<Parameter>,
+	blue_gain: Option<Parameter>,
+	shot_noise: Option<Parameter>,
+	read_noise: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct ChromaticAberration {
+	pub id: String,
+	shift: Parameter,
+	jitter: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Vignette {
+	pub id: String,
+	strength: Parameter,
+	radius: Option<Parameter>,
+	shape: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct FilmGrain {
+	pub id: String,
+	intensity: Parameter,
+	size: Option<Parameter>,
+	monochrome: Option<Choice<bool>>,
+	response: Option<Vec<(f32, f32)>>,
+}
@@ -1,15 +1,191 @@
 use super::*;
 
 use anyhow::Result;
+use anyhow::anyhow;
+
+use std::ops::Bound;
+
+/// Exactly one of quality and the two size targets sets the quality of an encode.
+fn validate_quality(id: &str, quality: &Option<Parameter>, target_bpp: &Option<Parameter>, target_size: &Option<Parameter>) -> Result<()> {
+	match (quality, target_bpp.is_some(), target_size.is_some()) {
+		(None, false, false) => Err(anyhow!("{}: one of quality, target_bpp or target_size is required", id)),
+		(Some(quality), false, false) if !quality.int_within(1..=100)? => Err(anyhow!("{}: quality must be between 1 and 100", id)),
+		(Some(_), false, false) | (None, true, false) | (None, false, true) => Ok(()),
+		_ => Err(anyhow!("{}: only one of quality, target_bpp or target_size can be set", id)),
+	}
+}
 
 impl Jpeg {
+	pub fn validate(&self) -> Result<()> {
+		validate_quality(&self.id, &self.quality, &self.target_bpp, &self.target_size)?;
+
+		Ok(())
+	}
 	pub fn quality(&self) -> Result<u32> {
-		self.quality.int()
+		self.quality.as_ref().ok_or(anyhow!("quality"))?.int()
+	}
+	pub fn target_bpp(&self) -> Result<Option<f32>> {
+		self.target_bpp.as_ref().map(|bpp| bpp.float()).transpose()
+	}
+	pub fn target_size(&self) -> Result<Option<u32>> {
+		self.target_size.as_ref().map(|size| size.int()).transpose()
+	}
+	pub fn subsampling(&self) -> Result<ChromaSubsampling> {
+		match self.subsampling.as_ref() {
+			Some(subsampling) => subsampling.get(),
+			None => Ok(ChromaSubsampling::Ratio420),
+		}
+	}
+	pub fn quantization(&self) -> Result<QuantizationTable> {
+		match self.quantization.as_ref() {
+			Some(quantization) => quantization.get(),
+			None => Ok(QuantizationTable::AnnexK),
+		}
+	}
+	pub fn progressive(&self) -> Result<bool> {
+		match self.progressive.as_ref() {
+			Some(progressive) => progressive.get(),
+			None => Ok(false),
+		}
 	}
 }
 
 impl WebP {
+	pub fn validate(&self) -> Result<()> {
+		validate_quality(&self.id, &self.quality, &self.target_bpp, &self.target_size)?;
+
+		// Lossless quality is compression effort, searching it for a size is meaningless
+		let targeted = self.target_bpp.is_some() || self.target_size.is_some();
+		let lossy = matches!(self.lossless, None | Some(Choice::Value(false)));
+
+		if targeted && !lossy {
+			return Err(anyhow!("{}: target_bpp and target_size can not be combined with lossless", self.id));
+		}
+
+		if let Some(method) = self.method.as_ref() {
+			if !method.int_within(0..=6)? {
+				return Err(anyhow!("{}: method must be between 0 and 6", self.id));
+			}
+		}
+
+		for option in [&self.alpha_quality, &self.near_lossless].into_iter().flatten() {
+			if !option.int_within(0..=100)? {
+				return Err(anyhow!("{}: alpha_quality and near_lossless must be between 0 and 100", self.id));
+			}
+		}
+
+		Ok(())
+	}
 	pub fn quality(&self) -> Result<u32> {
-		self.quality.int()
+		self.quality.as_ref().ok_or(anyhow!("quality"))?.int()
+	}
+	pub fn target_bpp(&self) -> Result<Option<f32>> {
+		self.target_bpp.as_ref().map(|bpp| bpp.float()).transpose()
+	}
+	pub fn target_size(&self) -> Result<Option<u32>> {
+		self.target_size.as_ref().map(|size| size.int()).transpose()
+	}
+	pub fn lossless(&self) -> Result<bool> {
+		match self.lossless.as_ref() {
+			Some(lossless) => lossless.get(),
+			None => Ok(false),
+		}
+	}
+	pub fn method(&self) -> Result<u32> {
+		match self.method.as_ref() {
+			Some(method) => method.int(),
+			None => Ok(4),
+		}
+	}
+	pub fn alpha_quality(&self) -> Result<u32> {
+		match self.alpha_quality.as_ref() {
+			Some(alpha_quality) => alpha_quality.int(),
+			None => Ok(100),
+		}
+	}
+	pub fn near_lossless(&self) -> Result<u32> {
+		match self.near_lossless.as_ref() {
+			Some(near_lossless) => near_lossless.int(),
+			None => Ok(100),
+		}
+	}
+	pub fn sharp_yuv(&self) -> Result<bool> {
+		match self.sharp_yuv.as_ref() {
+			Some(sharp_yuv) => sharp_yuv.get(),
+			None => Ok(false),
+		}
+	}
+}
+
+impl GenerationLoss {
+	pub fn validate(&self) -> Result<()> {
+		if self.codecs.is_empty() {
+			return Err(anyhow!("{}: codecs can not be empty", self.id));
+		}
+
+		if let Some(rescale) = self.rescale.as_ref() {
+			if !rescale.float_within((Bound::Excluded(0.0), Bound::Unbounded))? {
+				return Err(anyhow!("{}: rescale must be positive", self.id));
+			}
+		}
+
+		// Rounds rescale around the codec, anything that changes the image on its own does not belong here
+		for codec in self.codecs.iter() {
+			match codec {
+				ImageModifier::Jpeg(_) | ImageModifier::WebP(_) | ImageModifier::Qoi(_) | ImageModifier::Palette(_) => codec.validate()?,
+				_ => return Err(anyhow!("{}: {} is not a codec", self.id, codec.get().id())),
+			}
+		}
+
+		Ok(())
+	}
+	pub fn rounds(&self) -> Result<u32> {
+		self.rounds.int()
+	}
+	pub fn codecs(&self) -> &Vec<ImageModifier> {
+		&self.codecs
+	}
+	pub fn rescale(&self) -> Result<Option<f32>> {
+		self.rescale.as_ref().map(|rescale| rescale.float()).transpose()
+	}
+	pub fn filter(&self) -> Result<ResizeFilter> {
+		match self.filter.as_ref() {
+			Some(filter) => filter.get(),
+			None => Ok(ResizeFilter::Triangle),
+		}
+	}
+	pub fn shift(&self) -> Result<Option<u32>> {
+		self.shift.as_ref().map(|shift| shift.int()).transpose()
+	}
+}
+
+impl Palette {
+	pub fn validate(&self) -> Result<()> {
+		if !self.colors.int_within(2..=256)? {
+			return Err(anyhow!("{}: colors must be between 2 and 256", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn colors(&self) -> Result<u32> {
+		self.colors.int()
+	}
+	pub fn method(&self) -> Result<PaletteMethod> {
+		self.method.get()
+	}
+	pub fn dither(&self) -> Result<Dither> {
+		self.dither.get()
+	}
+	pub fn alpha(&self) -> Result<AlphaMode> {
+		match self.alpha.as_ref() {
+			Some(alpha) => alpha.get(),
+			None => Ok(AlphaMode::Preserve),
+		}
+	}
+	pub fn codec(&self) -> Result<PaletteCodec> {
+		match self.codec.as_ref() {
+			Some(codec) => codec.get(),
+			None => Ok(PaletteCodec::None),
+		}
 	}
 }
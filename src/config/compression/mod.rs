@@ -1,6 +1,7 @@
 use serde::Serialize;
 use serde::Deserialize;
 use super::enumerations::*;
+use super::ImageModifier;
 
 mod interface;
 
@@ -8,12 +9,52 @@ mod interface;
 #[derive(Serialize, Deserialize)]
 pub struct Jpeg {
 	pub id: String,
-	quality: Parameter,
+	quality: Option<Parameter>,
+	target_bpp: Option<Parameter>,
+	target_size: Option<Parameter>,
+	subsampling: Option<Choice<ChromaSubsampling>>,
+	quantization: Option<Choice<QuantizationTable>>,
+	progressive: Option<Choice<bool>>,
 }
 
 #[derive(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
 pub struct WebP {
 	pub id: String,
-	quality: Parameter,
+	quality: Option<Parameter>,
+	target_bpp: Option<Parameter>,
+	target_size: Option<Parameter>,
+	lossless: Option<Choice<bool>>,
+	method: Option<Parameter>,
+	alpha_quality: Option<Parameter>,
+	near_lossless: Option<Parameter>,
+	sharp_yuv: Option<Choice<bool>>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Qoi {
+	pub id: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct GenerationLoss {
+	pub id: String,
+	rounds: Parameter,
+	codecs: Vec<ImageModifier>,
+	rescale: Option<Parameter>,
+	filter: Option<Choice<ResizeFilter>>,
+	shift: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Palette {
+	pub id: String,
+	colors: Parameter,
+	method: Choice<PaletteMethod>,
+	dither: Choice<Dither>,
+	alpha: Option<Choice<AlphaMode>>,
+	codec: Option<Choice<PaletteCodec>>,
 }
@@ -24,6 +24,322 @@ pub enum SaveFormat {
 	Original,
 }
 
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum PngCompression {
+	Default,
+	Fast,
+	Best,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum PngFilter {
+	NoFilter,
+	Sub,
+	Up,
+	Avg,
+	Paeth,
+	Adaptive,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum BitDepth {
+	Original,
+	Eight,
+	Sixteen,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum ChromaSubsampling {
+	Ratio444,
+	Ratio440,
+	Ratio422,
+	Ratio420,
+	Ratio411,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum QuantizationTable {
+	AnnexK,
+	Flat,
+	MsSsim,
+	PsnrHvs,
+	ImageMagick,
+	KleinSilversteinCarney,
+	DentalXRays,
+	VisualDetectionModel,
+	ImprovedDetectionModel,
+	PhotoshopLow,
+	PhotoshopMaximum,
+	Nexus6P,
+	Random,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum PaletteMethod {
+	MedianCut,
+	NeuQuant,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum Dither {
+	None,
+	Bayer,
+	FloydSteinberg,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum AlphaMode {
+	Preserve,
+	Binarize,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum PaletteCodec {
+	None,
+	Gif,
+	Png8,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum PixelFormat {
+	Rgb565,
+	Rgb555,
+	Rgb444,
+	Rgb332,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum ColorSpace {
+	Rgb,
+	YCbCr601,
+	YCbCr709,
+	Hsv,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum Plane {
+	R,
+	G,
+	B,
+	Y,
+	Cb,
+	Cr,
+	H,
+	S,
+	V,
+	Alpha,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum PlaneGroup {
+	Luma,
+	Chroma,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum CfaPattern {
+	Rggb,
+	Bggr,
+	Grbg,
+	Gbrg,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum Demosaic {
+	Nearest,
+	Bilinear,
+	MalvarHeCutler,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum FlipDirection {
+	Horizontal,
+	Vertical,
+	Both,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum Interpolation {
+	Nearest,
+	Bilinear,
+	Bicubic,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum Border {
+	Constant,
+	Reflect,
+	Edge,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum CropPosition {
+	Center,
+	Random,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
+pub enum HalftoneMode {
+	Cmyk,
+	Gray,
+}
+
+/// Enumerations that can be sampled with [`Choice::Rand`] and [`Choice::RandWithout`].
+pub trait Variants: Sized + 'static {
+	const VARIANTS: &'static [Self];
+}
+
+impl Variants for bool {
+	const VARIANTS: &'static [Self] = &[false, true];
+}
+
+impl Variants for ResizeFilter {
+	const VARIANTS: &'static [Self] = &[
+		Self::Point,
+		Self::Catrom,
+		Self::BSpline,
+		Self::Mitchell,
+		Self::Lanczos3,
+		Self::Gaussian,
+		Self::Triangle,
+	];
+}
+
+impl Variants for ChromaSubsampling {
+	const VARIANTS: &'static [Self] = &[
+		Self::Ratio444,
+		Self::Ratio440,
+		Self::Ratio422,
+		Self::Ratio420,
+		Self::Ratio411,
+	];
+}
+
+impl Variants for QuantizationTable {
+	const VARIANTS: &'static [Self] = &[
+		Self::AnnexK,
+		Self::Flat,
+		Self::MsSsim,
+		Self::PsnrHvs,
+		Self::ImageMagick,
+		Self::KleinSilversteinCarney,
+		Self::DentalXRays,
+		Self::VisualDetectionModel,
+		Self::ImprovedDetectionModel,
+		Self::PhotoshopLow,
+		Self::PhotoshopMaximum,
+		Self::Nexus6P,
+		Self::Random,
+	];
+}
+
+impl Variants for PaletteMethod {
+	const VARIANTS: &'static [Self] = &[Self::MedianCut, Self::NeuQuant];
+}
+
+impl Variants for Dither {
+	const VARIANTS: &'static [Self] = &[Self::None, Self::Bayer, Self::FloydSteinberg];
+}
+
+impl Variants for AlphaMode {
+	const VARIANTS: &'static [Self] = &[Self::Preserve, Self::Binarize];
+}
+
+impl Variants for PaletteCodec {
+	const VARIANTS: &'static [Self] = &[Self::None, Self::Gif, Self::Png8];
+}
+
+impl Variants for PixelFormat {
+	const VARIANTS: &'static [Self] = &[Self::Rgb565, Self::Rgb555, Self::Rgb444, Self::Rgb332];
+}
+
+impl Variants for ColorSpace {
+	const VARIANTS: &'static [Self] = &[Self::Rgb, Self::YCbCr601, Self::YCbCr709, Self::Hsv];
+}
+
+impl Variants for PlaneGroup {
+	const VARIANTS: &'static [Self] = &[Self::Luma, Self::Chroma];
+}
+
+impl Variants for CfaPattern {
+	const VARIANTS: &'static [Self] = &[Self::Rggb, Self::Bggr, Self::Grbg, Self::Gbrg];
+}
+
+impl Variants for Demosaic {
+	const VARIANTS: &'static [Self] = &[Self::Nearest, Self::Bilinear, Self::MalvarHeCutler];
+}
+
+impl Variants for FlipDirection {
+	const VARIANTS: &'static [Self] = &[Self::Horizontal, Self::Vertical, Self::Both];
+}
+
+impl Variants for Interpolation {
+	const VARIANTS: &'static [Self] = &[Self::Nearest, Self::Bilinear, Self::Bicubic];
+}
+
+impl Variants for Border {
+	const VARIANTS: &'static [Self] = &[Self::Constant, Self::Reflect, Self::Edge];
+}
+
+impl Variants for CropPosition {
+	const VARIANTS: &'static [Self] = &[Self::Center, Self::Random];
+}
+
+impl Variants for HalftoneMode {
+	const VARIANTS: &'static [Self] = &[Self::Cmyk, Self::Gray];
+}
+
+/// Sampled value of an enumeration (or `bool`).
+#[derive(PartialEq)]
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub enum Choice<T> {
+	Value(T),
+	Rand,
+	RandFrom(Vec<T>),
+	RandWithout(Vec<T>),
+}
+
 #[derive(PartialEq)]
 #[derive(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
@@ -44,6 +360,12 @@ pub enum Parameter {
 	RandInt(usize, usize),
 	RandIntFrom(Vec<u32>),
 
+	Planes {
+		id: String,
+		space: ColorSpace,
+		planes: Vec<Plane>,
+	},
+
 	RandResizeFilter, // todo
 	RandResizeFilterFrom(Vec<ResizeFilter>), // todo
 	RandResizeFilterWithout(Vec<ResizeFilter>), // todo
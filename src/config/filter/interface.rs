@@ -1,6 +1,7 @@
 use super::*;
 
 use anyhow::Result;
+use anyhow::anyhow;
 
 impl BilateralFilter {
 	pub fn window_size(&self) -> Result<u32> {
@@ -44,3 +45,51 @@ impl MedianFilter {
 		self.y_radius.int()
 	}
 }
+
+impl SincFilter {
+	pub fn cutoff(&self) -> Result<f32> {
+		self.cutoff.float()
+	}
+	pub fn kernel_size(&self) -> Result<u32> {
+		self.kernel_size.int()
+	}
+}
+
+impl ChromaSubsample {
+	pub fn validate(&self) -> Result<()> {
+		if !self.space.candidates().iter().all(|space| is_ycbcr(*space)) {
+			return Err(anyhow!("{}: space must be YCbCr601 or YCbCr709", self.id));
+		}
+
+		if let Some(degradation) = self.degradation.as_ref() {
+			degradation.validate()?;
+		}
+
+		Ok(())
+	}
+	pub fn space(&self) -> Result<ColorSpace> {
+		self.space.get()
+	}
+	pub fn subsampling(&self) -> Result<ChromaSubsampling> {
+		self.subsampling.get()
+	}
+	pub fn downsample(&self) -> Result<ResizeFilter> {
+		self.downsample.get()
+	}
+	pub fn upsample(&self) -> Result<ResizeFilter> {
+		self.upsample.get()
+	}
+	pub fn degradation(&self) -> Option<&ImageModifier> {
+		self.degradation.as_ref()
+	}
+	pub fn target(&self) -> Result<PlaneGroup> {
+		match self.target.as_ref() {
+			Some(target) => target.get(),
+			None => Ok(PlaneGroup::Chroma),
+		}
+	}
+}
+
+fn is_ycbcr(space: ColorSpace) -> bool {
+	matches!(space, ColorSpace::YCbCr601 | ColorSpace::YCbCr709)
+}
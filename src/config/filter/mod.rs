@@ -1,4 +1,5 @@
 use super::enumerations::*;
+use super::ImageModifier;
 
 use serde::Serialize;
 use serde::Deserialize;
@@ -50,3 +51,23 @@ pub struct MedianFilter {
 	x_radius: Parameter,
 	y_radius: Parameter,
 }
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct SincFilter {
+	pub id: String,
+	cutoff: Parameter,
+	kernel_size: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct ChromaSubsample {
+	pub id: String,
+	space: Choice<ColorSpace>,
+	subsampling: Choice<ChromaSubsampling>,
+	downsample: Choice<ResizeFilter>,
+	upsample: Choice<ResizeFilter>,
+	degradation: Option<ImageModifier>,
+	target: Option<Choice<PlaneGroup>>,
+}
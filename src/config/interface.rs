@@ -3,6 +3,12 @@ use super::*;
 
 use std::path::Path;
 
+use anyhow::Result;
+use anyhow::anyhow;
+
+use imageproc::geometric_transformations;
+use resize::Type;
+
 impl ImageModifier {
 	pub fn get(&self) -> Box<dyn Modifier> {
 		match self.clone() {
@@ -12,18 +18,103 @@ impl ImageModifier {
 			Self::MedianFilter(m) => m,
 			Self::Sharpen3x3(m) => m,
 			Self::SharpenGaussian(m) => m,
+			Self::SincFilter(m) => m,
+			Self::ChromaSubsample(m) => m,
 
 			Self::Jpeg(m) => m,
 			Self::WebP(m) => m,
+			Self::Qoi(m) => m,
+			Self::GenerationLoss(m) => m,
+			Self::Palette(m) => m,
 
 			Self::Brighten(m) => m,
 			Self::Contrast(m) => m,
+			Self::HueShift(m) => m,
+			Self::Saturation(m) => m,
+			Self::Gamma(m) => m,
+			Self::WhiteBalance(m) => m,
+			Self::ColorCast(m) => m,
+			Self::LowLight(m) => m,
+			Self::Overexposure(m) => m,
 
 			Self::Resize(m) => m,
+			Self::MarkSize(m) => m,
+			Self::RestoreSize(m) => m,
+			Self::ProgressiveDownsample(m) => m,
+			Self::Pixelate(m) => m,
+			Self::Decimate(m) => m,
 			Self::ToLuma(m) => m,
 			Self::ToLumaAlpha(m) => m,
 			Self::ToRgb(m) => m,
 			Self::ToRgba(m) => m,
+			Self::Quantize(m) => m,
+			Self::Flip(m) => m,
+			Self::Rotate90(m) => m,
+			Self::Rotate(m) => m,
+			Self::Crop(m) => m,
+			Self::Pad(m) => m,
+			Self::Perspective(m) => m,
+			Self::Affine(m) => m,
+			Self::LensDistortion(m) => m,
+			Self::Mosaic(m) => m,
+			Self::CameraIsp(m) => m,
+			Self::ChromaticAberration(m) => m,
+			Self::Vignette(m) => m,
+			Self::FilmGrain(m) => m,
+
+			Self::LineJitter(m) => m,
+			Self::ChromaBleed(m) => m,
+			Self::TapeNoise(m) => m,
+			Self::Interlace(m) => m,
+			Self::Scanlines(m) => m,
+
+			Self::Halftone(m) => m,
+			Self::PrintScan(m) => m,
+
+			Self::Haze(m) => m,
+			Self::Rain(m) => m,
+			Self::Snow(m) => m,
+		}
+	}
+	/// Rejects settings that can not work for any image.
+	pub fn validate(&self) -> Result<()> {
+		match self {
+			Self::ChromaSubsample(m) => m.validate(),
+
+			Self::Jpeg(m) => m.validate(),
+			Self::WebP(m) => m.validate(),
+			Self::GenerationLoss(m) => m.validate(),
+			Self::Palette(m) => m.validate(),
+
+			Self::Gamma(m) => m.validate(),
+			Self::WhiteBalance(m) => m.validate(),
+			Self::LowLight(m) => m.validate(),
+			Self::Overexposure(m) => m.validate(),
+
+			Self::Resize(m) => m.validate(),
+			Self::ProgressiveDownsample(m) => m.validate(),
+			Self::Pixelate(m) => m.validate(),
+			Self::Decimate(m) => m.validate(),
+			Self::Quantize(m) => m.validate(),
+			Self::Crop(m) => m.validate(),
+
+			Self::ChromaticAberration(m) => m.validate(),
+			Self::Vignette(m) => m.validate(),
+			Self::FilmGrain(m) => m.validate(),
+
+			Self::LineJitter(m) => m.validate(),
+			Self::ChromaBleed(m) => m.validate(),
+			Self::TapeNoise(m) => m.validate(),
+			Self::Scanlines(m) => m.validate(),
+
+			Self::Halftone(m) => m.validate(),
+			Self::PrintScan(m) => m.validate(),
+
+			Self::Haze(m) => m.validate(),
+			Self::Rain(m) => m.validate(),
+			Self::Snow(m) => m.validate(),
+
+			_ => Ok(()),
 		}
 	}
 }
@@ -59,6 +150,116 @@ impl Output {
 	pub fn path(&self) -> &Path {
 		&self.path
 	}
+	pub fn format(&self) -> SaveFormat {
+		self.format
+	}
+	pub fn manifest(&self) -> Option<&Path> {
+		self.manifest.as_deref()
+	}
+	pub fn png(&self) -> PngOptions {
+		self.png.clone().unwrap_or_default()
+	}
+	pub fn jpeg(&self) -> JpegOptions {
+		self.jpeg.clone().unwrap_or_default()
+	}
+	pub fn webp(&self) -> WebPOptions {
+		self.webp.clone().unwrap_or_default()
+	}
+	pub fn depth(&self) -> BitDepth {
+		self.depth.unwrap_or(BitDepth::Original)
+	}
+}
+
+impl PngOptions {
+	pub fn compression(&self) -> PngCompression {
+		self.compression.unwrap_or(PngCompression::Default)
+	}
+	pub fn filter(&self) -> PngFilter {
+		self.filter.unwrap_or(PngFilter::Adaptive)
+	}
+}
+
+impl JpegOptions {
+	pub fn quality(&self) -> u8 {
+		self.quality.unwrap_or(100).clamp(1, 100)
+	}
+	pub fn subsampling(&self) -> ChromaSubsampling {
+		self.subsampling.unwrap_or(ChromaSubsampling::Ratio444)
+	}
+	/// JPEG has no alpha channel, so images with alpha are composited onto this color before encoding; without it they are rejected.
+	pub fn background(&self) -> Option<[u8; 3]> {
+		self.background
+	}
+}
+
+impl WebPOptions {
+	pub fn lossless(&self) -> bool {
+		self.lossless.unwrap_or(true)
+	}
+	pub fn quality(&self) -> u8 {
+		self.quality.unwrap_or(if self.lossless() { 75 } else { 100 }).min(100)
+	}
+}
+
+impl ChromaSubsampling {
+	/// Horizontal and vertical chroma downsampling factors.
+	pub fn factors(self) -> (u32, u32) {
+		match self {
+			Self::Ratio444 => (1, 1),
+			Self::Ratio440 => (1, 2),
+			Self::Ratio422 => (2, 1),
+			Self::Ratio420 => (2, 2),
+			Self::Ratio411 => (4, 1),
+		}
+	}
+}
+
+impl PixelFormat {
+	/// Red, green and blue bits per pixel.
+	pub fn bits(self) -> [u32; 3] {
+		match self {
+			Self::Rgb565 => [5, 6, 5],
+			Self::Rgb555 => [5, 5, 5],
+			Self::Rgb444 => [4, 4, 4],
+			Self::Rgb332 => [3, 3, 2],
+		}
+	}
+}
+
+impl CfaPattern {
+	/// Channel index (0 red, 1 green, 2 blue) of the 2x2 tile, indexed by `[y % 2][x % 2]`.
+	pub fn tile(self) -> [[usize; 2]; 2] {
+		match self {
+			Self::Rggb => [[0, 1], [1, 2]],
+			Self::Bggr => [[2, 1], [1, 0]],
+			Self::Grbg => [[1, 0], [2, 1]],
+			Self::Gbrg => [[1, 2], [0, 1]],
+		}
+	}
+}
+
+impl Interpolation {
+	pub fn interpolation_type(self) -> geometric_transformations::Interpolation {
+		match self {
+			Self::Nearest => geometric_transformations::Interpolation::Nearest,
+			Self::Bilinear => geometric_transformations::Interpolation::Bilinear,
+			Self::Bicubic => geometric_transformations::Interpolation::Bicubic,
+		}
+	}
+}
+
+impl ResizeFilter {
+	pub fn resize_type(self) -> Type {
+		match self {
+			Self::Point => Type::Point,
+			Self::Triangle => Type::Triangle,
+			Self::Catrom => Type::Catrom,
+			Self::Mitchell => Type::Mitchell,
+			Self::BSpline => Type::BSpline,
+			Self::Gaussian => Type::Gaussian,
+			Self::Lanczos3 => Type::Lanczos3,
+		}
+	}
 }
 
 impl ProgressSettings {
@@ -78,3 +279,22 @@ impl Sequence {
 		&self.elements
 	}
 }
+
+impl Plane {
+	/// Index of the plane in [`ColorSpace`] order, alpha always comes last.
+	pub fn index(self, space: ColorSpace) -> Result<usize> {
+		match (self, space) {
+			(Self::R, ColorSpace::Rgb) => Ok(0),
+			(Self::G, ColorSpace::Rgb) => Ok(1),
+			(Self::B, ColorSpace::Rgb) => Ok(2),
+			(Self::Y, ColorSpace::YCbCr601 | ColorSpace::YCbCr709) => Ok(0),
+			(Self::Cb, ColorSpace::YCbCr601 | ColorSpace::YCbCr709) => Ok(1),
+			(Self::Cr, ColorSpace::YCbCr601 | ColorSpace::YCbCr709) => Ok(2),
+			(Self::H, ColorSpace::Hsv) => Ok(0),
+			(Self::S, ColorSpace::Hsv) => Ok(1),
+			(Self::V, ColorSpace::Hsv) => Ok(2),
+			(Self::Alpha, _) => Ok(3),
+			(plane, space) => Err(anyhow!("plane {:?} is not part of {:?}", plane, space)),
+		}
+	}
+}
@@ -8,6 +8,10 @@ use self::filter::*;
 use self::adjustment::*;
 use self::compression::*;
 use self::operation::*;
+use self::camera::*;
+use self::analog::*;
+use self::print::*;
+use self::weather::*;
 
 pub mod interface;
 pub mod enumerations;
@@ -16,6 +20,10 @@ pub mod filter;
 pub mod compression;
 pub mod adjustment;
 pub mod operation;
+pub mod camera;
+pub mod analog;
+pub mod print;
+pub mod weather;
 
 #[derive(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
@@ -26,18 +34,63 @@ pub enum ImageModifier {
 	MedianFilter(Box<MedianFilter>),
 	BilateralFilter(Box<BilateralFilter>),
 	SharpenGaussian(Box<SharpenGaussian>),
+	SincFilter(Box<SincFilter>),
+	ChromaSubsample(Box<ChromaSubsample>),
 
 	Jpeg(Box<Jpeg>),
 	WebP(Box<WebP>),
+	Qoi(Box<Qoi>),
+	GenerationLoss(Box<GenerationLoss>),
+	Palette(Box<Palette>),
 
 	Brighten(Box<Brighten>),
 	Contrast(Box<Contrast>),
+	HueShift(Box<HueShift>),
+	Saturation(Box<Saturation>),
+	Gamma(Box<Gamma>),
+	WhiteBalance(Box<WhiteBalance>),
+	ColorCast(Box<ColorCast>),
+	LowLight(Box<LowLight>),
+	Overexposure(Box<Overexposure>),
 
 	Resize(Box<Resize>),
+	MarkSize(Box<MarkSize>),
+	RestoreSize(Box<RestoreSize>),
+	ProgressiveDownsample(Box<ProgressiveDownsample>),
+	Pixelate(Box<Pixelate>),
+	Decimate(Box<Decimate>),
 	ToLumaAlpha(Box<ToLumaAlpha>),
 	ToLuma(Box<ToLuma>),
 	ToRgb(Box<ToRgb>),
 	ToRgba(Box<ToRgba>),
+	Quantize(Box<Quantize>),
+	Flip(Box<Flip>),
+	Rotate90(Box<Rotate90>),
+	Rotate(Box<Rotate>),
+	Crop(Box<Crop>),
+	Pad(Box<Pad>),
+	Perspective(Box<Perspective>),
+	Affine(Box<Affine>),
+	LensDistortion(Box<LensDistortion>),
+
+	Mosaic(Box<Mosaic>),
+	CameraIsp(Box<CameraIsp>),
+	ChromaticAberration(Box<ChromaticAberration>),
+	Vignette(Box<Vignette>),
+	FilmGrain(Box<FilmGrain>),
+
+	LineJitter(Box<LineJitter>),
+	ChromaBleed(Box<ChromaBleed>),
+	TapeNoise(Box<TapeNoise>),
+	Interlace(Box<Interlace>),
+	Scanlines(Box<Scanlines>),
+
+	Halftone(Box<Halftone>),
+	PrintScan(Box<PrintScan>),
+
+	Haze(Box<Haze>),
+	Rain(Box<Rain>),
+	Snow(Box<Snow>),
 }
 
 #[derive(Debug)]
@@ -66,6 +119,34 @@ pub struct Output {
 
 	naming: FileName,
 	format: SaveFormat,
+	manifest: Option<PathBuf>,
+
+	png: Option<PngOptions>,
+	jpeg: Option<JpegOptions>,
+	webp: Option<WebPOptions>,
+	depth: Option<BitDepth>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[derive(Serialize, Deserialize)]
+pub struct PngOptions {
+	compression: Option<PngCompression>,
+	filter: Option<PngFilter>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[derive(Serialize, Deserialize)]
+pub struct JpegOptions {
+	quality: Option<u8>,
+	subsampling: Option<ChromaSubsampling>,
+	background: Option<[u8; 3]>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[derive(Serialize, Deserialize)]
+pub struct WebPOptions {
+	lossless: Option<bool>,
+	quality: Option<u8>,
 }
 
 #[derive(Debug, Clone)]
@@ -2,8 +2,17 @@ use super::*;
 use resize::Type;
 use anyhow::Result;
 use anyhow::anyhow;
+use std::ops::Bound;
 
 impl Resize {
+	pub fn validate(&self) -> Result<()> {
+		match (self.width.as_ref(), self.height.as_ref(), self.scale.as_ref()) {
+			(Some(_), Some(_), _) => Ok(()),
+			(None, None, Some(scale)) if scale.float_within((Bound::Excluded(0.0), Bound::Unbounded))? => Ok(()),
+			(None, None, Some(_)) => Err(anyhow!("{}: scale must be positive", self.id)),
+			_ => Err(anyhow!("{}: either width and height or scale is required", self.id)),
+		}
+	}
 	pub fn width(&self) -> Result<u32> {
 		if let Some(width) = self.width.as_ref() { width.int() } else { Err(anyhow!("width error")) }
 	}
@@ -14,15 +23,319 @@ impl Resize {
 			Err(anyhow!("height error "))
 		}
 	}
+	pub fn size(&self, width: u32, height: u32) -> Result<(u32, u32)> {
+		if self.width.is_none() && self.height.is_none() {
+			if let Some(scale) = self.scale.as_ref() {
+				let scale = scale.float()?;
+				let scaled = |size: u32| ((size as f32 * scale).round() as u32).max(1);
+
+				return Ok((scaled(width), scaled(height)));
+			}
+		}
+
+		Ok((self.width()?, self.height()?))
+	}
 	pub fn filter(&self) -> Type {
-		match self.filter {
-			ResizeFilter::Point => Type::Point,
-			ResizeFilter::Triangle => Type::Triangle,
-			ResizeFilter::Catrom => Type::Catrom,
-			ResizeFilter::Mitchell => Type::Mitchell,
-			ResizeFilter::BSpline => Type::BSpline,
-			ResizeFilter::Gaussian => Type::Gaussian,
-			ResizeFilter::Lanczos3 => Type::Lanczos3,
+		self.filter.resize_type()
+	}
+}
+
+impl Quantize {
+	pub fn validate(&self) -> Result<()> {
+		match (self.format.as_ref(), self.bits.as_ref()) {
+			(Some(_), _) => Ok(()),
+			(None, Some(bits)) if bits.int_within(1..=16)? => Ok(()),
+			(None, Some(_)) => Err(anyhow!("{}: bits must be between 1 and 16", self.id)),
+			(None, None) => Err(anyhow!("{}: one of bits or format is required", self.id)),
+		}
+	}
+	pub fn bits(&self) -> Result<[u32; 3]> {
+		if let Some(format) = self.format.as_ref() {
+			return Ok(format.get()?.bits());
+		}
+
+		Ok([self.bits.as_ref().ok_or(anyhow!("bits"))?.int()?; 3])
+	}
+	pub fn dither(&self) -> Result<Dither> {
+		match self.dither.as_ref() {
+			Some(dither) => dither.get(),
+			None => Ok(Dither::None),
+		}
+	}
+}
+
+impl Flip {
+	pub fn direction(&self) -> Result<FlipDirection> {
+		self.direction.get()
+	}
+}
+
+impl Rotate90 {
+	pub fn turns(&self) -> Result<u32> {
+		Ok(self.turns.int()? % 4)
+	}
+}
+
+impl Rotate {
+	pub fn angle(&self) -> Result<f32> {
+		self.angle.float()
+	}
+	pub fn interpolation(&self) -> Result<Interpolation> {
+		match self.interpolation.as_ref() {
+			Some(interpolation) => interpolation.get(),
+			None => Ok(Interpolation::Bilinear),
+		}
+	}
+	pub fn border(&self) -> Result<Border> {
+		match self.border.as_ref() {
+			Some(border) => border.get(),
+			None => Ok(Border::Constant),
+		}
+	}
+	pub fn value(&self) -> Result<f32> {
+		match self.value.as_ref() {
+			Some(value) => value.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl Crop {
+	pub fn validate(&self) -> Result<()> {
+		if self.x.is_some() != self.y.is_some() {
+			return Err(anyhow!("{}: crop needs both x and y", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn width(&self) -> Result<u32> {
+		self.width.int()
+	}
+	pub fn height(&self) -> Result<u32> {
+		self.height.int()
+	}
+	pub fn position(&self) -> Result<CropPosition> {
+		match self.position.as_ref() {
+			Some(position) => position.get(),
+			None => Ok(CropPosition::Center),
+		}
+	}
+	pub fn origin(&self) -> Result<Option<(u32, u32)>> {
+		match (self.x.as_ref(), self.y.as_ref()) {
+			(Some(x), Some(y)) => Ok(Some((x.int()?, y.int()?))),
+			_ => Ok(None),
+		}
+	}
+}
+
+impl Pad {
+	pub fn margins(&self) -> Result<[u32; 4]> {
+		let mut margins = [0; 4];
+
+		for (margin, parameter) in margins.iter_mut().zip([&self.top, &self.bottom, &self.left, &self.right]) {
+			if let Some(parameter) = parameter {
+				*margin = parameter.int()?;
+			}
+		}
+
+		Ok(margins)
+	}
+	pub fn border(&self) -> Result<Border> {
+		match self.border.as_ref() {
+			Some(border) => border.get(),
+			None => Ok(Border::Constant),
+		}
+	}
+	pub fn value(&self) -> Result<f32> {
+		match self.value.as_ref() {
+			Some(value) => value.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl Perspective {
+	pub fn strength(&self) -> Result<f32> {
+		self.strength.float()
+	}
+	pub fn interpolation(&self) -> Result<Interpolation> {
+		match self.interpolation.as_ref() {
+			Some(interpolation) => interpolation.get(),
+			None => Ok(Interpolation::Bilinear),
+		}
+	}
+	pub fn border(&self) -> Result<Border> {
+		match self.border.as_ref() {
+			Some(border) => border.get(),
+			None => Ok(Border::Constant),
+		}
+	}
+	pub fn value(&self) -> Result<f32> {
+		match self.value.as_ref() {
+			Some(value) => value.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl Affine {
+	pub fn rotation(&self) -> Result<f32> {
+		match self.rotation.as_ref() {
+			Some(rotation) => rotation.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn scale(&self) -> Result<f32> {
+		match self.scale.as_ref() {
+			Some(scale) => scale.float(),
+			None => Ok(1.0),
+		}
+	}
+	pub fn shear(&self) -> Result<f32> {
+		match self.shear.as_ref() {
+			Some(shear) => shear.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn translation(&self) -> Result<(f32, f32)> {
+		let x = match self.translate_x.as_ref() {
+			Some(x) => x.float()?,
+			None => 0.0,
+		};
+		let y = match self.translate_y.as_ref() {
+			Some(y) => y.float()?,
+			None => 0.0,
+		};
+
+		Ok((x, y))
+	}
+	pub fn interpolation(&self) -> Result<Interpolation> {
+		match self.interpolation.as_ref() {
+			Some(interpolation) => interpolation.get(),
+			None => Ok(Interpolation::Bilinear),
+		}
+	}
+	pub fn border(&self) -> Result<Border> {
+		match self.border.as_ref() {
+			Some(border) => border.get(),
+			None => Ok(Border::Constant),
+		}
+	}
+	pub fn value(&self) -> Result<f32> {
+		match self.value.as_ref() {
+			Some(value) => value.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl LensDistortion {
+	pub fn coefficients(&self) -> Result<(f32, f32)> {
+		let k2 = match self.k2.as_ref() {
+			Some(k2) => k2.float()?,
+			None => 0.0,
+		};
+
+		Ok((self.k1.float()?, k2))
+	}
+	pub fn interpolation(&self) -> Result<Interpolation> {
+		match self.interpolation.as_ref() {
+			Some(interpolation) => interpolation.get(),
+			None => Ok(Interpolation::Bilinear),
+		}
+	}
+	pub fn border(&self) -> Result<Border> {
+		match self.border.as_ref() {
+			Some(border) => border.get(),
+			None => Ok(Border::Constant),
+		}
+	}
+	pub fn value(&self) -> Result<f32> {
+		match self.value.as_ref() {
+			Some(value) => value.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl RestoreSize {
+	pub fn mark(&self) -> &str {
+		self.mark.as_ref()
+	}
+	pub fn filter(&self) -> Result<ResizeFilter> {
+		self.filter.get()
+	}
+}
+
+impl ProgressiveDownsample {
+	pub fn validate(&self) -> Result<()> {
+		if !self.scale.float_within((Bound::Excluded(0.0), Bound::Unbounded))? {
+			return Err(anyhow!("{}: scale must be positive", self.id));
+		}
+
+		if !self.steps.int_within(1..)? {
+			return Err(anyhow!("{}: steps must be at least 1", self.id));
+		}
+
+		if let Some(shift) = self.shift.as_ref() {
+			if !shift.float_within(0.0..)? {
+				return Err(anyhow!("{}: shift can not be negative", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn scale(&self) -> Result<f32> {
+		self.scale.float()
+	}
+	pub fn steps(&self) -> Result<u32> {
+		self.steps.int()
+	}
+	pub fn filter(&self) -> Result<ResizeFilter> {
+		self.filter.get()
+	}
+	pub fn shift(&self) -> Result<f32> {
+		match self.shift.as_ref() {
+			Some(shift) => shift.float(),
+			None => Ok(0.0),
+		}
+	}
+}
+
+impl Pixelate {
+	pub fn validate(&self) -> Result<()> {
+		if !self.size.int_within(1..)? {
+			return Err(anyhow!("{}: size must be at least 1", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn size(&self) -> Result<u32> {
+		self.size.int()
+	}
+	pub fn random_offset(&self) -> Result<bool> {
+		match self.random_offset.as_ref() {
+			Some(random_offset) => random_offset.get(),
+			None => Ok(false),
+		}
+	}
+}
+
+impl Decimate {
+	pub fn validate(&self) -> Result<()> {
+		if !self.factor.int_within(1..)? {
+			return Err(anyhow!("{}: factor must be at least 1", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn factor(&self) -> Result<u32> {
+		self.factor.int()
+	}
+	pub fn random_offset(&self) -> Result<bool> {
+		match self.random_offset.as_ref() {
+			Some(random_offset) => random_offset.get(),
+			None => Ok(false),
 		}
 	}
 }
@@ -10,6 +10,7 @@ pub struct Resize {
 	pub id: String,
 	width: Option<Parameter>,
 	height: Option<Parameter>,
+	scale: Option<Parameter>,
 	filter: ResizeFilter,
 }
 
@@ -36,3 +37,134 @@ pub struct ToRgba {
 pub struct ToRgb {
 	pub id: String,
 }
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Quantize {
+	pub id: String,
+	bits: Option<Parameter>,
+	format: Option<Choice<PixelFormat>>,
+	dither: Option<Choice<Dither>>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Flip {
+	pub id: String,
+	direction: Choice<FlipDirection>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Rotate90 {
+	pub id: String,
+	turns: Parameter,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Rotate {
+	pub id: String,
+	angle: Parameter,
+	interpolation: Option<Choice<Interpolation>>,
+	border: Option<Choice<Border>>,
+	value: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Crop {
+	pub id: String,
+	width: Parameter,
+	height: Parameter,
+	position: Option<Choice<CropPosition>>,
+	x: Option<Parameter>,
+	y: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Pad {
+	pub id: String,
+	top: Option<Parameter>,
+	bottom: Option<Parameter>,
+	left: Option<Parameter>,
+	right: Option<Parameter>,
+	border: Option<Choice<Border>>,
+	value: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Perspective {
+	pub id: String,
+	strength: Parameter,
+	interpolation: Option<Choice<Interpolation>>,
+	border: Option<Choice<Border>>,
+	value: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Affine {
+	pub id: String,
+	rotation: Option<Parameter>,
+	scale: Option<Parameter>,
+	shear: Option<Parameter>,
+	translate_x: Option<Parameter>,
+	translate_y: Option<Parameter>,
+	interpolation: Option<Choice<Interpolation>>,
+	border: Option<Choice<Border>>,
+	value: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct LensDistortion {
+	pub id: String,
+	k1: Parameter,
+	k2: Option<Parameter>,
+	interpolation: Option<Choice<Interpolation>>,
+	border: Option<Choice<Border>>,
+	value: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct MarkSize {
+	pub id: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct RestoreSize {
+	pub id: String,
+	mark: String,
+	filter: Choice<ResizeFilter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct ProgressiveDownsample {
+	pub id: String,
+	scale: Parameter,
+	steps: Parameter,
+	filter: Choice<ResizeFilter>,
+	shift: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Pixelate {
+	pub id: String,
+	size: Parameter,
+	random_offset: Option<Choice<bool>>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Decimate {
+	pub id: String,
+	factor: Parameter,
+	random_offset: Option<Choice<bool>>,
+}
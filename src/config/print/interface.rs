@@ -0,0 +1,95 @@
+use super::*;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+impl Halftone {
+	pub fn validate(&self) -> Result<()> {
+		if !self.dot_size.float_within(1.0..)? {
+			return Err(anyhow!("{}: dot_size must be at least 1", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn dot_size(&self) -> Result<f32> {
+		self.dot_size.float()
+	}
+	pub fn mode(&self) -> Result<HalftoneMode> {
+		match self.mode.as_ref() {
+			Some(mode) => mode.get(),
+			None => Ok(HalftoneMode::Cmyk),
+		}
+	}
+	pub fn angle(&self) -> Result<f32> {
+		match self.angle.as_ref() {
+			Some(angle) => angle.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn angles(&self) -> [f32; 4] {
+		// Conventional cyan, magenta, yellow and black screens that keep the moiré between inks small
+		self.angles.unwrap_or([15.0, 75.0, 0.0, 45.0])
+	}
+}
+
+impl PrintScan {
+	pub fn validate(&self) -> Result<()> {
+		if let Some(grain) = self.grain.as_ref() {
+			if !grain.float_within(0.0..)? {
+				return Err(anyhow!("{}: grain can not be negative", self.id));
+			}
+		}
+
+		if let Some(illumination) = self.illumination.as_ref() {
+			if !illumination.float_within(0.0..=1.0)? {
+				return Err(anyhow!("{}: illumination must be between 0 and 1", self.id));
+			}
+		}
+
+		if let Some(blur) = self.blur.as_ref() {
+			match blur {
+				ImageModifier::BoxFilter(_)
+				| ImageModifier::Sharpen3x3(_)
+				| ImageModifier::GaussianBlur(_)
+				| ImageModifier::MedianFilter(_)
+				| ImageModifier::BilateralFilter(_)
+				| ImageModifier::SharpenGaussian(_)
+				| ImageModifier::SincFilter(_) => blur.validate()?,
+				_ => return Err(anyhow!("{}: blur must be a filter", self.id)),
+			}
+		}
+
+		if let Some(resize) = self.resize.as_ref() {
+			match resize {
+				ImageModifier::Resize(_) | ImageModifier::ProgressiveDownsample(_) => resize.validate()?,
+				_ => return Err(anyhow!("{}: resize must be Resize or ProgressiveDownsample", self.id)),
+			}
+		}
+
+		Ok(())
+	}
+	pub fn grain(&self) -> Result<f32> {
+		match self.grain.as_ref() {
+			Some(grain) => grain.float(),
+			None => Ok(0.03),
+		}
+	}
+	pub fn illumination(&self) -> Result<f32> {
+		match self.illumination.as_ref() {
+			Some(illumination) => illumination.float(),
+			None => Ok(0.1),
+		}
+	}
+	pub fn skew(&self) -> Result<f32> {
+		match self.skew.as_ref() {
+			Some(skew) => skew.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn blur(&self) -> Option<&ImageModifier> {
+		self.blur.as_ref()
+	}
+	pub fn resize(&self) -> Option<&ImageModifier> {
+		self.resize.as_ref()
+	}
+}
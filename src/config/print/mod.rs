@@ -0,0 +1,27 @@
+use serde::Serialize;
+use serde::Deserialize;
+use super::enumerations::*;
+use super::ImageModifier;
+
+mod interface;
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Halftone {
+	pub id: String,
+	dot_size: Parameter,
+	mode: Option<Choice<HalftoneMode>>,
+	angle: Option<Parameter>,
+	angles: Option<[f32; 4]>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct PrintScan {
+	pub id: String,
+	grain: Option<Parameter>,
+	illumination: Option<Parameter>,
+	skew: Option<Parameter>,
+	blur: Option<ImageModifier>,
+	resize: Option<ImageModifier>,
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+use std::path::Path;
+
+impl Haze {
+	pub fn validate(&self) -> Result<()> {
+		if !self.density.float_within(0.0..)? {
+			return Err(anyhow!("{}: density can not be negative", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn density(&self) -> Result<f32> {
+		self.density.float()
+	}
+	pub fn airlight(&self) -> Result<f32> {
+		match self.airlight.as_ref() {
+			Some(airlight) => airlight.float(),
+			None => Ok(0.9),
+		}
+	}
+	pub fn depth(&self) -> Option<&Path> {
+		self.depth.as_deref()
+	}
+}
+
+impl Rain {
+	pub fn validate(&self) -> Result<()> {
+		if !self.density.float_within(0.0..)? {
+			return Err(anyhow!("{}: density can not be negative", self.id));
+		}
+
+		if !self.length.float_within(1.0..)? {
+			return Err(anyhow!("{}: length must be at least 1", self.id));
+		}
+
+		Ok(())
+	}
+	pub fn density(&self) -> Result<f32> {
+		self.density.float()
+	}
+	pub fn length(&self) -> Result<f32> {
+		self.length.float()
+	}
+	pub fn angle(&self) -> Result<f32> {
+		match self.angle.as_ref() {
+			Some(angle) => angle.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn intensity(&self) -> Result<f32> {
+		match self.intensity.as_ref() {
+			Some(intensity) => intensity.float(),
+			None => Ok(0.6),
+		}
+	}
+}
+
+impl Snow {
+	pub fn validate(&self) -> Result<()> {
+		if !self.density.float_within(0.0..)? {
+			return Err(anyhow!("{}: density can not be negative", self.id));
+		}
+
+		if !self.size.float_within(0.5..)? {
+			return Err(anyhow!("{}: size must be at least 0.5", self.id));
+		}
+
+		if let Some(length) = self.length.as_ref() {
+			if !length.float_within(0.0..)? {
+				return Err(anyhow!("{}: length can not be negative", self.id));
+			}
+		}
+
+		Ok(())
+	}
+	pub fn density(&self) -> Result<f32> {
+		self.density.float()
+	}
+	pub fn size(&self) -> Result<f32> {
+		self.size.float()
+	}
+	pub fn length(&self) -> Result<f32> {
+		match self.length.as_ref() {
+			Some(length) => length.float(),
+			None => Ok(0.0),
+		}
+	}
+	pub fn angle(&self) -> Result<f32> {
+		match self.angle.as_ref() {
+			Some(angle) => angle.float(),
+			None => Ok(0.0),
+		}
+	}
+}
@@ -0,0 +1,36 @@
+use serde::Serialize;
+use serde::Deserialize;
+use super::enumerations::*;
+
+use std::path::PathBuf;
+
+mod interface;
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Haze {
+	pub id: String,
+	density: Parameter,
+	airlight: Option<Parameter>,
+	depth: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Rain {
+	pub id: String,
+	density: Parameter,
+	length: Parameter,
+	angle: Option<Parameter>,
+	intensity: Option<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Snow {
+	pub id: String,
+	density: Parameter,
+	size: Parameter,
+	length: Option<Parameter>,
+	angle: Option<Parameter>,
+}
@@ -2,6 +2,17 @@ use crate::config::adjustment::*;
 use super::{Image, Modifier};
 
 use anyhow::Result;
+use anyhow::anyhow;
+
+use imageproc::definitions::Clamp;
+use image::DynamicImage;
+use image::DynamicImage::*;
+use image::ImageBuffer;
+use image::Pixel;
+use image::Primitive;
+
+use conv::ValueInto;
+use rand::prelude::*;
 
 impl Modifier for Brighten {
 	fn apply(&self, image: &mut Image) -> Result<()> {
@@ -28,3 +39,293 @@ impl Modifier for Contrast {
 		self.id.as_ref()
 	}
 }
+
+impl Modifier for HueShift {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let degrees = self.degrees()?;
+
+		*image.source_mut() = map_colors(image.source(), |rgb| {
+			let [h, s, v] = rgb_to_hsv(rgb);
+			hsv_to_rgb([h + degrees, s, v])
+		})?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Saturation {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let factor = self.factor()?;
+
+		*image.source_mut() = map_colors(image.source(), |rgb| {
+			let luma = luma(rgb);
+			rgb.map(|c| luma + (c - luma) * factor)
+		})?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Gamma {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let gamma = self.gamma()?;
+
+		*image.source_mut() = map_colors(image.source(), |rgb| rgb.map(|c| c.powf(gamma)))?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for WhiteBalance {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let mut gains = self.gains()?;
+
+		if let Some(temperature) = self.temperature()? {
+			let white = kelvin_to_rgb(6500.0);
+			let tint = kelvin_to_rgb(temperature);
+
+			// Normalized to green so the overall brightness stays roughly the same
+			let green = tint[1] / white[1];
+
+			for ((gain, tint), white) in gains.iter_mut().zip(tint).zip(white) {
+				*gain *= tint / white / green;
+			}
+		}
+
+		*image.source_mut() = map_colors(image.source(), |rgb| {
+			[rgb[0] * gains[0], rgb[1] * gains[1], rgb[2] * gains[2]]
+		})?;
+
+		image.record(self.id(), "gains", gains)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for ColorCast {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let strength = self.strength()?;
+		let hue = match self.hue()? {
+			Some(hue) => hue,
+			None => thread_rng().gen_range(0.0..360.0),
+		};
+
+		let cast = hsv_to_rgb([hue, 1.0, 1.0]);
+		let mean = cast.iter().sum::<f32>() / 3.0;
+		let offset = cast.map(|c| (c - mean) * strength);
+
+		*image.source_mut() = map_colors(image.source(), |rgb| {
+			[rgb[0] + offset[0], rgb[1] + offset[1], rgb[2] + offset[2]]
+		})?;
+
+		image.record(self.id(), "hue", hue)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for LowLight {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let exposure = self.exposure()?;
+		let gamma = self.gamma()?;
+		let shot_noise = self.shot_noise()?;
+		let read_noise = self.read_noise()?;
+
+		let mut rng = thread_rng();
+
+		*image.source_mut() = map_colors(image.source(), |rgb| {
+			rgb.map(|c| {
+				let linear = srgb_to_linear(c) * exposure;
+				let sigma = (shot_noise * linear + read_noise * read_noise).sqrt();
+				let noisy = (linear + sigma * standard_normal(&mut rng)).clamp(0.0, 1.0);
+
+				// Quantized as an 8-bit capture regardless of the source depth
+				let encoded = linear_to_srgb(noisy).powf(gamma);
+				(encoded * 255.0).round() / 255.0
+			})
+		})?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Overexposure {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let exposure = self.exposure()?;
+		let clip = self.clip()?;
+
+		// The clip point becomes full scale, like a sensor saturating below its maximum
+		*image.source_mut() = map_colors(image.source(), |rgb| {
+			rgb.map(|c| linear_to_srgb((srgb_to_linear(c) * exposure).min(clip) / clip))
+		})?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Applies `f` to every pixel as normalized RGB, luma images are expanded to
+/// gray RGB and converted back afterwards. Alpha is left untouched.
+fn map_colors<F>(source: &DynamicImage, mut f: F) -> Result<DynamicImage> where F: FnMut([f32; 3]) -> [f32; 3] {
+	match source {
+		ImageLuma8(image) => Ok(ImageLuma8(map_pixels(image, &mut f))),
+		ImageLuma16(image) => Ok(ImageLuma16(map_pixels(image, &mut f))),
+		ImageLumaA8(image) => Ok(ImageLumaA8(map_pixels(image, &mut f))),
+		ImageLumaA16(image) => Ok(ImageLumaA16(map_pixels(image, &mut f))),
+		ImageRgb8(image) => Ok(ImageRgb8(map_pixels(image, &mut f))),
+		ImageRgb16(image) => Ok(ImageRgb16(map_pixels(image, &mut f))),
+		ImageRgb32F(image) => Ok(ImageRgb32F(map_pixels(image, &mut f))),
+		ImageRgba8(image) => Ok(ImageRgba8(map_pixels(image, &mut f))),
+		ImageRgba16(image) => Ok(ImageRgba16(map_pixels(image, &mut f))),
+		ImageRgba32F(image) => Ok(ImageRgba32F(map_pixels(image, &mut f))),
+		_ => Err(anyhow!("unsupported color")),
+	}
+}
+
+fn map_pixels<P, F>(image: &ImageBuffer<P, Vec<P::Subpixel>>, f: &mut F) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+	P: Pixel,
+	P::Subpixel: ValueInto<f32> + Clamp<f32>,
+	F: FnMut([f32; 3]) -> [f32; 3],
+{
+	let scale: f32 = P::Subpixel::DEFAULT_MAX_VALUE.value_into().unwrap_or(1.0);
+	// Integer samples are rounded, float samples are only clamped
+	let offset = if scale > 1.0 { 0.5 } else { 0.0 };
+
+	let normalize = |c: P::Subpixel| -> f32 { c.value_into().unwrap_or(0.0) / scale };
+	let denormalize = |c: f32| -> P::Subpixel { Clamp::clamp(c.clamp(0.0, 1.0) * scale + offset) };
+
+	let mut output = image.clone();
+
+	for pixel in output.pixels_mut() {
+		let channels = pixel.channels_mut();
+
+		if P::CHANNEL_COUNT >= 3 {
+			let rgb = f([normalize(channels[0]), normalize(channels[1]), normalize(channels[2])]);
+
+			for (channel, value) in channels.iter_mut().zip(rgb) {
+				*channel = denormalize(value);
+			}
+		} else {
+			let rgb = f([normalize(channels[0]); 3]);
+			channels[0] = denormalize(luma(rgb));
+		}
+	}
+
+	output
+}
+
+fn luma([r, g, b]: [f32; 3]) -> f32 {
+	0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Hue in degrees, saturation and value in `0..=1`.
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+	let max = r.max(g).max(b);
+	let delta = max - r.min(g).min(b);
+
+	let hue = if delta == 0.0 {
+		0.0
+	} else if max == r {
+		((g - b) / delta).rem_euclid(6.0)
+	} else if max == g {
+		(b - r) / delta + 2.0
+	} else {
+		(r - g) / delta + 4.0
+	};
+
+	let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+	[hue * 60.0, saturation, max]
+}
+
+fn hsv_to_rgb([h, s, v]: [f32; 3]) -> [f32; 3] {
+	let hue = (h / 60.0).rem_euclid(6.0);
+
+	let chroma = v * s;
+	let x = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+	let m = v - chroma;
+
+	let [r, g, b] = match hue as u32 {
+		0 => [chroma, x, 0.0],
+		1 => [x, chroma, 0.0],
+		2 => [0.0, chroma, x],
+		3 => [0.0, x, chroma],
+		4 => [x, 0.0, chroma],
+		_ => [chroma, 0.0, x],
+	};
+
+	[r + m, g + m, b + m]
+}
+
+/// Approximate normalized RGB of a black body at the given temperature
+/// (Tanner Helland's fit, valid from 1000K to 40000K).
+fn kelvin_to_rgb(kelvin: f32) -> [f32; 3] {
+	let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+	let r = if t <= 66.0 {
+		255.0
+	} else {
+		329.698_73 * (t - 60.0).powf(-0.133_204_76)
+	};
+
+	let g = if t <= 66.0 {
+		99.470_8 * t.ln() - 161.119_57
+	} else {
+		288.122_16 * (t - 60.0).powf(-0.075_514_846)
+	};
+
+	let b = if t >= 66.0 {
+		255.0
+	} else if t <= 19.0 {
+		0.0
+	} else {
+		138.517_73 * (t - 10.0).ln() - 305.044_8
+	};
+
+	[r, g, b].map(|c: f32| c.clamp(1.0, 255.0) / 255.0)
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		((c + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+	if c <= 0.003_130_8 {
+		c * 12.92
+	} else {
+		1.055 * c.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+/// Standard normal sample using the Box-Muller transform.
+pub fn standard_normal<R>(rng: &mut R) -> f32 where R: Rng {
+	let u: f32 = 1.0 - rng.gen::<f32>();
+	let v: f32 = rng.gen();
+
+	(-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
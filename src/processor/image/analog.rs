@@ -0,0 +1,220 @@
+use crate::config::analog::*;
+use crate::config::enumerations::BitDepth;
+use crate::config::enumerations::Border;
+use crate::config::enumerations::ColorSpace;
+use super::adjustment::standard_normal;
+use super::operation::remap;
+use super::convert_layout;
+use super::Image;
+use super::Modifier;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+use image::DynamicImage::*;
+use image::ImageBuffer;
+use image::Luma;
+use image::Primitive;
+
+use imageproc::definitions::Clamp;
+use imageproc::geometric_transformations::Interpolation;
+use conv::ValueInto;
+use rand::prelude::*;
+
+use std::f32::consts::PI;
+
+impl Modifier for LineJitter {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let amplitude = self.amplitude()?;
+		let correlation = self.correlation()?;
+		let height = image.height();
+
+		let mut rng = thread_rng();
+
+		// Offsets drift from line to line like a wobbling horizontal sync
+		let innovation = (1.0 - correlation * correlation).sqrt();
+		let mut offset = amplitude * standard_normal(&mut rng);
+		let mut offsets = Vec::with_capacity(height as usize);
+
+		for _ in 0..height {
+			offsets.push(offset);
+			offset = correlation * offset + innovation * amplitude * standard_normal(&mut rng);
+		}
+
+		let mapping = |x: f32, y: f32| (x - offsets[(y as usize).min(offsets.len() - 1)], y);
+		*image.source_mut() = remap(image.source(), mapping, Interpolation::Bilinear, Border::Edge, 0.0)?;
+
+		image.record(self.id(), "amplitude", amplitude)?;
+		image.record(self.id(), "correlation", correlation)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for ChromaBleed {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		// Grayscale images carry no chroma to smear
+		if !image.color().has_color() {
+			return Ok(());
+		}
+
+		let bleed = self.bleed()?;
+		let delay = self.delay()?;
+
+		// One-sided exponential smear, the chroma trails to the right like a band-limited signal
+		let decay = if bleed > 0.0 { (-1.0 / bleed).exp() } else { 0.0 };
+
+		let mut planes = image.separate_planes(ColorSpace::YCbCr601)?;
+
+		for plane in planes[1..3].iter_mut() {
+			*plane = match plane {
+				ImageLuma8(plane) => ImageLuma8(smear(plane, decay, delay)),
+				ImageLuma16(plane) => ImageLuma16(smear(plane, decay, delay)),
+				_ => return Err(anyhow!("unsupported plane")),
+			};
+		}
+
+		*image.source_mut() = image.combine_planes(ColorSpace::YCbCr601, planes)?;
+
+		image.record(self.id(), "bleed", bleed)?;
+		image.record(self.id(), "delay", delay)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for TapeNoise {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let bands = self.bands()?;
+		let band_height = self.height()?;
+		let intensity = self.intensity()?;
+		let color = image.color();
+
+		let mut buffer = image.source().to_rgba32f();
+		let (width, height) = buffer.dimensions();
+		let mut rng = thread_rng();
+
+		let positions: Vec<u32> = (0..bands).map(|_| rng.gen_range(0..height)).collect();
+
+		for &top in positions.iter() {
+			for line in 0..band_height.min(height - top) {
+				// Bands fade in and out vertically
+				let envelope = (PI * (line as f32 + 0.5) / band_height as f32).sin();
+				let y = top + line;
+
+				let mut x = 0;
+
+				while x < width {
+					// Streaks are held for a random run, mostly dim with occasional dropouts
+					let run = rng.gen_range(1..=(width / 8).max(1));
+					let streak = rng.gen::<f32>().powi(3);
+
+					for x in x..(x + run).min(width) {
+						let value = intensity * envelope * (streak + 0.25 * standard_normal(&mut rng));
+
+						for channel in buffer.get_pixel_mut(x, y).0[..3].iter_mut() {
+							*channel += value;
+						}
+					}
+
+					x += run;
+				}
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "bands", positions)?;
+		image.record(self.id(), "height", band_height)?;
+		image.record(self.id(), "intensity", intensity)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Interlace {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let motion = self.motion()?;
+
+		// The bottom field was captured later, so odd lines show the scene moved
+		let mapping = |x: f32, y: f32| if y as u32 % 2 == 1 { (x - motion, y) } else { (x, y) };
+		*image.source_mut() = remap(image.source(), mapping, Interpolation::Bilinear, Border::Edge, 0.0)?;
+
+		image.record(self.id(), "motion", motion)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Scanlines {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let strength = self.strength()?;
+		let period = self.period()?;
+		let phase = thread_rng().gen_range(0..period);
+		let color = image.color();
+
+		let mut buffer = image.source().to_rgba32f();
+
+		for (_, y, pixel) in buffer.enumerate_pixels_mut() {
+			let angle = 2.0 * PI * ((y + phase) % period) as f32 / period as f32;
+			let gain = 1.0 - strength * (0.5 + 0.5 * angle.cos());
+
+			for channel in pixel.0[..3].iter_mut() {
+				*channel *= gain;
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "strength", strength)?;
+		image.record(self.id(), "period", period)?;
+		image.record(self.id(), "phase", phase)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Linear sample along a line, positions outside take the edge value.
+fn sample_line(line: &[f32], x: f32) -> f32 {
+	let x = x.clamp(0.0, (line.len() - 1) as f32);
+	let x0 = x.floor() as usize;
+	let x1 = (x0 + 1).min(line.len() - 1);
+	let t = x - x0 as f32;
+
+	line[x0] * (1.0 - t) + line[x1] * t
+}
+
+/// Delays every row of the plane by `delay` pixels and runs it through a one-pole low-pass.
+fn smear<S>(plane: &ImageBuffer<Luma<S>, Vec<S>>, decay: f32, delay: f32) -> ImageBuffer<Luma<S>, Vec<S>>
+where
+	S: Primitive + ValueInto<f32> + Clamp<f32>,
+{
+	let mut output = plane.clone();
+
+	for (row, output) in plane.rows().zip(output.rows_mut()) {
+		let line: Vec<f32> = row.map(|pixel| pixel[0].value_into().unwrap_or(0.0)).collect();
+		let mut previous = sample_line(&line, -delay);
+
+		for (x, pixel) in output.enumerate() {
+			previous = decay * previous + (1.0 - decay) * sample_line(&line, x as f32 - delay);
+			pixel[0] = Clamp::clamp(previous);
+		}
+	}
+
+	output
+}
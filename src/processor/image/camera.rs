@@ -0,0 +1,611 @@
+use crate::config::camera::*;
+use crate::config::enumerations::BitDepth;
+use crate::config::enumerations::Border;
+use crate::config::enumerations::CfaPattern;
+use crate::config::enumerations::Demosaic;
+use super::adjustment::standard_normal;
+use super::convert_layout;
+use super::operation::reflect;
+use super::operation::remap;
+use super::Image;
+use super::Modifier;
+
+use anyhow::Result;
+
+use image::DynamicImage::*;
+use image::ImageBuffer;
+use image::Luma;
+use image::Rgb;
+use image::Rgb32FImage;
+
+use imageproc::geometric_transformations::Interpolation;
+use rand::prelude::*;
+
+/// Single channel sensor data, normalized to `0..=1`.
+pub type RawImage = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+const GRAIN_LAG: i64 = 3;
+
+// Malvar-He-Cutler gradient-corrected kernels, scaled by 8
+const G_AT_RB: [[f32; 5]; 5] = [
+	[0.0, 0.0, -1.0, 0.0, 0.0],
+	[0.0, 0.0, 2.0, 0.0, 0.0],
+	[-1.0, 2.0, 4.0, 2.0, -1.0],
+	[0.0, 0.0, 2.0, 0.0, 0.0],
+	[0.0, 0.0, -1.0, 0.0, 0.0],
+];
+const RB_AT_G_ROW: [[f32; 5]; 5] = [
+	[0.0, 0.0, 0.5, 0.0, 0.0],
+	[0.0, -1.0, 0.0, -1.0, 0.0],
+	[-1.0, 4.0, 5.0, 4.0, -1.0],
+	[0.0, -1.0, 0.0, -1.0, 0.0],
+	[0.0, 0.0, 0.5, 0.0, 0.0],
+];
+const RB_AT_G_COLUMN: [[f32; 5]; 5] = [
+	[0.0, 0.0, -1.0, 0.0, 0.0],
+	[0.0, -1.0, 4.0, -1.0, 0.0],
+	[0.5, 0.0, 5.0, 0.0, 0.5],
+	[0.0, -1.0, 4.0, -1.0, 0.0],
+	[0.0, 0.0, -1.0, 0.0, 0.0],
+];
+const RB_AT_BR: [[f32; 5]; 5] = [
+	[0.0, 0.0, -1.5, 0.0, 0.0],
+	[0.0, 2.0, 0.0, 2.0, 0.0],
+	[-1.5, 0.0, 6.0, 0.0, -1.5],
+	[0.0, 2.0, 0.0, 2.0, 0.0],
+	[0.0, 0.0, -1.5, 0.0, 0.0],
+];
+
+// Camera matrices and sRGB (D65) to XYZ from Brooks et al., "Unprocessing Images for Learned Raw Denoising"
+const XYZ_TO_CAM: [[[f32; 3]; 3]; 4] = [
+	[[1.0234, -0.2969, -0.2266], [-0.5625, 1.6328, -0.0469], [-0.0703, 0.2188, 0.6406]],
+	[[0.4913, -0.0541, -0.0202], [-0.613, 1.3513, 0.2906], [-0.1564, 0.2151, 0.7183]],
+	[[0.838, -0.263, -0.0639], [-0.2887, 1.0725, 0.2496], [-0.0627, 0.1427, 0.5438]],
+	[[0.6596, -0.2079, -0.0562], [-0.4782, 1.3016, 0.1933], [-0.097, 0.1581, 0.5181]],
+];
+const RGB_TO_XYZ: [[f32; 3]; 3] = [
+	[0.412_456_4, 0.357_576_1, 0.180_437_5],
+	[0.212_672_9, 0.715_152_2, 0.072_175],
+	[0.019_333_9, 0.119_192, 0.950_304_1],
+];
+
+impl Modifier for Mosaic {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let pattern = self.pattern()?;
+		let method = self.demosaic()?;
+		let noise = self.noise()?;
+		let color = image.color();
+
+		let mut raw = mosaic(&image.source().to_rgb32f(), pattern);
+
+		if noise > 0.0 {
+			let mut rng = thread_rng();
+
+			for sample in raw.iter_mut() {
+				*sample += noise * standard_normal(&mut rng);
+			}
+		}
+
+		let rgb = demosaic(&raw, pattern, method);
+		let mut buffer = image.source().to_rgba32f();
+
+		for (pixel, demosaiced) in buffer.pixels_mut().zip(rgb.pixels()) {
+			pixel.0[..3].copy_from_slice(&demosaiced.0);
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "pattern", pattern)?;
+		image.record(self.id(), "demosaic", method)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Unprocesses the image into noisy raw sensor data and develops it again.
+/// Unset gains and noise levels are sampled like Brooks et al. do.
+impl Modifier for CameraIsp {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let mut rng = thread_rng();
+
+		let pattern = self.pattern()?;
+		let method = self.demosaic()?;
+
+		let rgb_gain = match self.rgb_gain()? {
+			Some(gain) => gain,
+			None => 1.0 / (0.8 + 0.1 * standard_normal(&mut rng)),
+		};
+		let red_gain = match self.red_gain()? {
+			Some(gain) => gain,
+			None => rng.gen_range(1.9..2.4),
+		};
+		let blue_gain = match self.blue_gain()? {
+			Some(gain) => gain,
+			None => rng.gen_range(1.5..1.9),
+		};
+
+		let log_shot = match self.shot_noise()? {
+			Some(shot) => shot.ln(),
+			None => rng.gen_range(0.0001f32.ln()..0.012f32.ln()),
+		};
+		let shot_noise = log_shot.exp();
+		let read_noise = match self.read_noise()? {
+			Some(read) => read,
+			None => (2.18 * log_shot + 1.2 + 0.26 * standard_normal(&mut rng)).exp().sqrt(),
+		};
+
+		let rgb_to_cam = random_ccm(&mut rng);
+		let cam_to_rgb = invert(&rgb_to_cam);
+		let gains = [red_gain * rgb_gain, rgb_gain, blue_gain * rgb_gain];
+
+		let mut rgb = image.source().to_rgb32f();
+
+		// Inverse of the forward pipeline, from display referred sRGB back to linear camera space
+		for pixel in rgb.pixels_mut() {
+			let linear = pixel.0.map(|c| (0.5 - ((1.0 - 2.0 * c.clamp(0.0, 1.0)).asin() / 3.0).sin()).max(1e-8).powf(2.2));
+			let camera = multiply(&rgb_to_cam, linear);
+
+			// Highlights keep their gains so they don't turn gray after white balance
+			let mean = camera.iter().sum::<f32>() / 3.0;
+			let mask = ((mean - 0.9).max(0.0) / 0.1).powi(2);
+
+			let inverse = gains.map(|gain| {
+				let inverse = 1.0 / gain;
+				(mask + (1.0 - mask) * inverse).max(inverse)
+			});
+
+			pixel.0 = [camera[0] * inverse[0], camera[1] * inverse[1], camera[2] * inverse[2]];
+		}
+
+		let mut raw = mosaic(&rgb, pattern);
+
+		for sample in raw.iter_mut() {
+			let sigma = (shot_noise * sample.max(0.0) + read_noise * read_noise).sqrt();
+			*sample = (*sample + sigma * standard_normal(&mut rng)).clamp(0.0, 1.0);
+		}
+
+		let tile = pattern.tile();
+
+		for (x, y, sample) in raw.enumerate_pixels_mut() {
+			let gain = gains[tile[(y % 2) as usize][(x % 2) as usize]];
+			sample[0] = (sample[0] * gain).clamp(0.0, 1.0);
+		}
+
+		let mut rgb = demosaic(&raw, pattern, method);
+
+		for pixel in rgb.pixels_mut() {
+			let linear = multiply(&cam_to_rgb, pixel.0);
+
+			pixel.0 = linear.map(|c| {
+				let encoded = c.max(1e-8).powf(1.0 / 2.2).clamp(0.0, 1.0);
+				3.0 * encoded * encoded - 2.0 * encoded * encoded * encoded
+			});
+		}
+
+		let color = image.color();
+		let mut buffer = image.source().to_rgba32f();
+
+		for (pixel, developed) in buffer.pixels_mut().zip(rgb.pixels()) {
+			pixel.0[..3].copy_from_slice(&developed.0);
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "pattern", pattern)?;
+		image.record(self.id(), "demosaic", method)?;
+		image.record(self.id(), "ccm", rgb_to_cam)?;
+		image.record(self.id(), "rgb_gain", rgb_gain)?;
+		image.record(self.id(), "red_gain", red_gain)?;
+		image.record(self.id(), "blue_gain", blue_gain)?;
+		image.record(self.id(), "shot_noise", shot_noise)?;
+		image.record(self.id(), "read_noise", read_noise)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for ChromaticAberration {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let color = image.color();
+
+		// Without separate channels there is nothing to misalign
+		if !color.has_color() {
+			return Ok(());
+		}
+
+		let shift = self.shift()?;
+		let jitter = self.jitter()?;
+
+		let (width, height) = (image.width() as f32, image.height() as f32);
+		let mut rng = thread_rng();
+
+		let cx = (width - 1.0) / 2.0 + rng.gen_range(-1.0f32..=1.0) * jitter * width;
+		let cy = (height - 1.0) / 2.0 + rng.gen_range(-1.0f32..=1.0) * jitter * height;
+
+		// Red and blue are scaled in opposite directions around the center, green stays.
+		// Beyond half the radius the blue scale would collapse towards zero.
+		let radius = (width * width + height * height).sqrt().max(2.0) / 2.0;
+		let shift = shift.clamp(-0.5 * radius, 0.5 * radius);
+		let scales = [(0, 1.0 + shift / radius), (2, 1.0 - shift / radius)];
+
+		let source = ImageRgba32F(image.source().to_rgba32f());
+		let mut buffer = source.to_rgba32f();
+
+		for (channel, scale) in scales {
+			let mapping = |x: f32, y: f32| (cx + (x - cx) / scale, cy + (y - cy) / scale);
+			let scaled = remap(&source, mapping, Interpolation::Bilinear, Border::Edge, 0.0)?.into_rgba32f();
+
+			for (pixel, scaled) in buffer.pixels_mut().zip(scaled.pixels()) {
+				pixel[channel] = scaled[channel];
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "shift", shift)?;
+		image.record(self.id(), "center", [cx, cy])?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Vignette {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let strength = self.strength()?;
+		let radius = self.radius()?;
+		let shape = self.shape()?;
+		let color = image.color();
+
+		let cx = (image.width() as f32 - 1.0) / 2.0;
+		let cy = (image.height() as f32 - 1.0) / 2.0;
+		let diagonal = (cx * cx + cy * cy).sqrt().max(1.0);
+
+		let mut buffer = image.source().to_rgba32f();
+
+		for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+			let distance = ((x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)).sqrt() / diagonal;
+			let falloff = ((distance - radius) / (1.0 - radius)).clamp(0.0, 1.0).powf(shape);
+			let gain = 1.0 - strength * falloff;
+
+			for channel in pixel.0[..3].iter_mut() {
+				*channel *= gain;
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for FilmGrain {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let intensity = self.intensity()?;
+		let size = self.size()?;
+		let color = image.color();
+		let monochrome = self.monochrome()? || !color.has_color();
+		let response = self.response()?;
+
+		let (width, height) = (image.width(), image.height());
+		let coefficients = grain_coefficients(size);
+		let mut rng = thread_rng();
+
+		let fields: Vec<Vec<f32>> = (0..if monochrome { 1 } else { 3 })
+			.map(|_| grain_field(width, height, &coefficients, &mut rng))
+			.collect();
+
+		let mut buffer = image.source().to_rgba32f();
+
+		for (index, pixel) in buffer.pixels_mut().enumerate() {
+			let [r, g, b, _] = pixel.0;
+			let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+			let scale = intensity * response_at(&response, luma);
+
+			for (channel, value) in pixel.0[..3].iter_mut().enumerate() {
+				let field = &fields[channel.min(fields.len() - 1)];
+				*value += scale * field[index];
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "intensity", intensity)?;
+		image.record(self.id(), "size", size)?;
+		image.record(self.id(), "monochrome", monochrome)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Keeps only the channel the color filter array passes at every pixel.
+pub fn mosaic(rgb: &Rgb32FImage, pattern: CfaPattern) -> RawImage {
+	let tile = pattern.tile();
+
+	RawImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+		Luma([rgb.get_pixel(x, y)[tile[(y % 2) as usize][(x % 2) as usize]]])
+	})
+}
+
+/// Interpolates the two missing channels of every pixel. Borders are mirrored
+/// without repeating the edge, which keeps the CFA phase intact.
+pub fn demosaic(raw: &RawImage, pattern: CfaPattern, method: Demosaic) -> Rgb32FImage {
+	let tile = pattern.tile();
+	let (width, height) = raw.dimensions();
+
+	let channel_at = |x: i64, y: i64| tile[y.rem_euclid(2) as usize][x.rem_euclid(2) as usize];
+	let sample = |x: i64, y: i64| raw.get_pixel(reflect(x, width), reflect(y, height))[0];
+
+	let convolve = |x: i64, y: i64, kernel: &[[f32; 5]; 5]| {
+		let mut sum = 0.0;
+
+		for (dy, row) in kernel.iter().enumerate() {
+			for (dx, weight) in row.iter().enumerate() {
+				if *weight != 0.0 {
+					sum += weight * sample(x + dx as i64 - 2, y + dy as i64 - 2);
+				}
+			}
+		}
+
+		sum / 8.0
+	};
+
+	Rgb32FImage::from_fn(width, height, |x, y| {
+		let (x, y) = (x as i64, y as i64);
+		let own = channel_at(x, y);
+		let mut rgb = [0.0; 3];
+
+		for (channel, value) in rgb.iter_mut().enumerate() {
+			if channel == own {
+				*value = sample(x, y);
+				continue;
+			}
+
+			*value = match method {
+				Demosaic::Nearest => {
+					// Prefers the sample in the same row of the 2x2 tile
+					[(x ^ 1, y), (x, y ^ 1), (x ^ 1, y ^ 1)]
+						.into_iter()
+						.find(|(x, y)| channel_at(*x, *y) == channel)
+						.map(|(x, y)| sample(x, y))
+						.unwrap_or(0.0)
+				}
+				Demosaic::Bilinear => {
+					let (mut sum, mut count) = (0.0, 0.0);
+
+					for ny in y - 1..=y + 1 {
+						for nx in x - 1..=x + 1 {
+							if channel_at(nx, ny) == channel {
+								sum += sample(nx, ny);
+								count += 1.0;
+							}
+						}
+					}
+
+					sum / count
+				}
+				Demosaic::MalvarHeCutler => {
+					let kernel = if channel == 1 {
+						&G_AT_RB
+					} else if own != 1 {
+						&RB_AT_BR
+					} else if channel_at(x + 1, y) == channel {
+						&RB_AT_G_ROW
+					} else {
+						&RB_AT_G_COLUMN
+					};
+
+					convolve(x, y, kernel)
+				}
+			};
+		}
+
+		Rgb(rgb)
+	})
+}
+
+/// Random sRGB to camera matrix, a convex combination of the reference cameras
+/// with rows normalized so white stays white.
+fn random_ccm<R>(rng: &mut R) -> [[f32; 3]; 3] where R: Rng {
+	let weights: Vec<f32> = XYZ_TO_CAM.iter().map(|_| rng.gen_range(1e-8..1.0)).collect();
+	let total: f32 = weights.iter().sum();
+
+	let mut xyz_to_cam = [[0.0; 3]; 3];
+
+	for (matrix, weight) in XYZ_TO_CAM.iter().zip(weights) {
+		for (row, source) in xyz_to_cam.iter_mut().zip(matrix) {
+			for (value, source) in row.iter_mut().zip(source) {
+				*value += source * weight / total;
+			}
+		}
+	}
+
+	let mut rgb_to_cam = [[0.0; 3]; 3];
+
+	for (row, xyz) in rgb_to_cam.iter_mut().zip(xyz_to_cam) {
+		for (column, value) in row.iter_mut().enumerate() {
+			*value = (0..3).map(|k| xyz[k] * RGB_TO_XYZ[k][column]).sum();
+		}
+
+		let sum: f32 = row.iter().sum();
+		*row = row.map(|value| value / sum);
+	}
+
+	rgb_to_cam
+}
+
+fn multiply(matrix: &[[f32; 3]; 3], [r, g, b]: [f32; 3]) -> [f32; 3] {
+	matrix.map(|row| row[0] * r + row[1] * g + row[2] * b)
+}
+
+fn invert(m: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+	let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+
+	let adjugate = [
+		[cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+		[-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+		[cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+	];
+
+	let determinant = m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+
+	adjugate.map(|row| row.map(|value| value / determinant))
+}
+
+/// Causal neighbourhood of the AV1 grain model with lag 3: the three rows above
+/// and the three pixels to the left, as `(dx, dy, weight)`.
+fn grain_coefficients(size: f32) -> Vec<(i64, i64, f32)> {
+	if size <= 0.0 {
+		return Vec::new();
+	}
+
+	let mut coefficients = Vec::new();
+
+	for dy in -GRAIN_LAG..=0 {
+		for dx in -GRAIN_LAG..=GRAIN_LAG {
+			if dy == 0 && dx >= 0 {
+				break;
+			}
+
+			let weight = (-((dx * dx + dy * dy) as f32) / (2.0 * size * size)).exp();
+			coefficients.push((dx, dy, weight));
+		}
+	}
+
+	// Coefficients summing below one keep the filter stable, larger grain correlates further
+	let total: f32 = coefficients.iter().map(|(_, _, weight)| weight).sum();
+	let gain = (1.0 - (-size / 2.0).exp()).min(0.9) / total;
+
+	for (_, _, weight) in coefficients.iter_mut() {
+		*weight *= gain;
+	}
+
+	coefficients
+}
+
+/// Filters gaussian noise with the autoregressive coefficients and normalizes it
+/// to unit variance. The field is generated with a margin so the borders settle.
+fn grain_field<R>(width: u32, height: u32, coefficients: &[(i64, i64, f32)], rng: &mut R) -> Vec<f32> where R: Rng {
+	let margin = GRAIN_LAG as usize * 4;
+	let padded_width = width as usize + 2 * margin;
+	let padded_height = height as usize + margin;
+
+	let mut field = vec![0.0f32; padded_width * padded_height];
+
+	for y in 0..padded_height {
+		for x in 0..padded_width {
+			let mut value = standard_normal(rng);
+
+			for &(dx, dy, weight) in coefficients {
+				let (sx, sy) = (x as i64 + dx, y as i64 + dy);
+
+				if sx >= 0 && sy >= 0 && (sx as usize) < padded_width {
+					value += weight * field[sy as usize * padded_width + sx as usize];
+				}
+			}
+
+			field[y * padded_width + x] = value;
+		}
+	}
+
+	let mut grain = Vec::with_capacity(width as usize * height as usize);
+
+	for y in margin..padded_height {
+		grain.extend_from_slice(&field[y * padded_width + margin..y * padded_width + margin + width as usize]);
+	}
+
+	let count = grain.len().max(1) as f32;
+	let mean = grain.iter().sum::<f32>() / count;
+	let deviation = (grain.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / count).sqrt().max(f32::EPSILON);
+
+	for value in grain.iter_mut() {
+		*value = (*value - mean) / deviation;
+	}
+
+	grain
+}
+
+/// Linear interpolation between the sorted response points, constant past the ends.
+fn response_at(points: &[(f32, f32)], luma: f32) -> f32 {
+	let (first, last) = match (points.first(), points.last()) {
+		(Some(first), Some(last)) => (first, last),
+		_ => return 1.0,
+	};
+
+	if luma <= first.0 {
+		return first.1;
+	}
+
+	for pair in points.windows(2) {
+		let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+
+		if luma <= x1 {
+			let t = (luma - x0) / (x1 - x0);
+			return y0 + t * (y1 - y0);
+		}
+	}
+
+	last.1
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::config::enumerations::Variants;
+
+	#[test]
+	fn demosaic_reproduces_constant_color() {
+		let color = [0.8, 0.5, 0.2];
+		let rgb = Rgb32FImage::from_pixel(9, 7, Rgb(color));
+
+		for pattern in CfaPattern::VARIANTS {
+			for method in Demosaic::VARIANTS {
+				let restored = demosaic(&mosaic(&rgb, *pattern), *pattern, *method);
+
+				for pixel in restored.pixels() {
+					for (value, expected) in pixel.0.iter().zip(color) {
+						assert!((value - expected).abs() < 1e-5, "{:?} {:?} gave {:?}", pattern, method, pixel);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn camera_matrix_inverts() {
+		let mut rng = StdRng::seed_from_u64(7);
+		let rgb_to_cam = random_ccm(&mut rng);
+		let cam_to_rgb = invert(&rgb_to_cam);
+
+		for channel in 0..3 {
+			let mut rgb = [0.0; 3];
+			rgb[channel] = 1.0;
+
+			let restored = multiply(&cam_to_rgb, multiply(&rgb_to_cam, rgb));
+
+			for (value, expected) in restored.iter().zip(rgb) {
+				assert!((value - expected).abs() < 1e-4);
+			}
+		}
+
+		// Rows are normalized so white stays white
+		for value in multiply(&rgb_to_cam, [1.0; 3]) {
+			assert!((value - 1.0).abs() < 1e-5);
+		}
+	}
+}
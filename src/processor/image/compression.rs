@@ -1,31 +1,159 @@
 use super::Image;
+use super::remove_alpha;
+use super::restore_alpha;
+use super::restore_layout;
+use super::BAYER_8X8;
 use crate::config::compression::*;
+use crate::config::enumerations::ChromaSubsampling;
+use crate::config::enumerations::QuantizationTable;
+use crate::config::enumerations::PaletteMethod;
+use crate::config::enumerations::PaletteCodec;
+use crate::config::enumerations::AlphaMode;
+use crate::config::enumerations::Dither;
+use crate::config::enumerations::Border;
+use super::operation::pad_image;
+use super::operation::resize_image;
 
 use image::DynamicImage;
+use image::DynamicImage::*;
+use image::RgbaImage;
+use image::Rgba;
 use image::codecs as codecs;
 use codecs::jpeg::JpegDecoder;
-use codecs::jpeg::JpegEncoder;
+use codecs::gif::GifDecoder;
+use codecs::gif::GifEncoder;
+use codecs::png::PngDecoder;
+use codecs::qoi::QoiDecoder;
+use codecs::qoi::QoiEncoder;
 use codecs::webp::WebPDecoder;
-use codecs::webp::WebPEncoder;
-use codecs::webp::WebPQuality;
 use std::io::Cursor;
 
+use jpeg_encoder::ColorType;
+use jpeg_encoder::Encoder;
+use jpeg_encoder::QuantizationTableType;
+use jpeg_encoder::SamplingFactor;
+
+use color_quant::NeuQuant;
+use webp::WebPConfig;
+use rand::prelude::*;
+
 use anyhow::Result;
+use anyhow::anyhow;
 use super::Modifier;
 
+// Sample tables given in Annex K of ITU-T T.81, in natural order.
+const ANNEX_K_LUMA: [u16; 64] = [
+	16, 11, 10, 16, 24, 40, 51, 61,
+	12, 12, 14, 19, 26, 58, 60, 55,
+	14, 13, 16, 24, 40, 57, 69, 56,
+	14, 17, 22, 29, 51, 87, 80, 62,
+	18, 22, 37, 56, 68, 109, 103, 77,
+	24, 35, 55, 64, 81, 104, 113, 92,
+	49, 64, 78, 87, 103, 121, 120, 101,
+	72, 92, 95, 98, 112, 100, 103, 99,
+];
+const ANNEX_K_CHROMA: [u16; 64] = [
+	17, 18, 24, 47, 99, 99, 99, 99,
+	18, 21, 26, 66, 99, 99, 99, 99,
+	24, 26, 56, 99, 99, 99, 99, 99,
+	47, 66, 99, 99, 99, 99, 99, 99,
+	99, 99, 99, 99, 99, 99, 99, 99,
+	99, 99, 99, 99, 99, 99, 99, 99,
+	99, 99, 99, 99, 99, 99, 99, 99,
+	99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+// Tables read from JPEGs written by other encoders, in natural order.
+// Photoshop at quality 0, from a file carrying a Photoshop resource block.
+const PHOTOSHOP_LOW_LUMA: [u16; 64] = [
+	32, 33, 51, 81, 66, 39, 34, 17,
+	33, 36, 48, 47, 28, 23, 12, 12,
+	51, 48, 47, 28, 23, 12, 12, 12,
+	81, 47, 28, 23, 12, 12, 12, 12,
+	66, 28, 23, 12, 12, 12, 12, 12,
+	39, 23, 12, 12, 12, 12, 12, 12,
+	34, 12, 12, 12, 12, 12, 12, 12,
+	17, 12, 12, 12, 12, 12, 12, 12,
+];
+const PHOTOSHOP_LOW_CHROMA: [u16; 64] = [
+	34, 51, 52, 34, 20, 20, 17, 17,
+	51, 38, 24, 14, 14, 12, 12, 12,
+	52, 24, 14, 14, 12, 12, 12, 12,
+	34, 14, 14, 12, 12, 12, 12, 12,
+	20, 14, 12, 12, 12, 12, 12, 12,
+	20, 12, 12, 12, 12, 12, 12, 12,
+	17, 12, 12, 12, 12, 12, 12, 12,
+	17, 12, 12, 12, 12, 12, 12, 12,
+];
+// Highest quality tables found in files with an Adobe APP14 marker.
+const PHOTOSHOP_MAXIMUM_LUMA: [u16; 64] = [
+	1, 1, 1, 1, 1, 1, 1, 1,
+	1, 1, 1, 1, 1, 1, 1, 1,
+	1, 1, 1, 1, 1, 1, 1, 2,
+	1, 1, 1, 1, 1, 1, 2, 2,
+	1, 1, 1, 1, 1, 2, 2, 3,
+	1, 1, 1, 1, 2, 2, 3, 3,
+	1, 1, 1, 2, 2, 3, 3, 3,
+	1, 1, 2, 2, 3, 3, 3, 3,
+];
+const PHOTOSHOP_MAXIMUM_CHROMA: [u16; 64] = [
+	1, 1, 1, 2, 2, 3, 3, 3,
+	1, 1, 1, 2, 3, 3, 3, 3,
+	1, 1, 1, 3, 3, 3, 3, 3,
+	2, 2, 3, 3, 3, 3, 3, 3,
+	2, 3, 3, 3, 3, 3, 3, 3,
+	3, 3, 3, 3, 3, 3, 3, 3,
+	3, 3, 3, 3, 3, 3, 3, 3,
+	3, 3, 3, 3, 3, 3, 3, 3,
+];
+// Camera original of a Nexus 6P with HDR+.
+const NEXUS_6P_LUMA: [u16; 64] = [
+	2, 1, 1, 2, 2, 4, 5, 6,
+	1, 1, 1, 2, 3, 6, 6, 6,
+	1, 1, 2, 2, 4, 6, 7, 6,
+	1, 2, 2, 3, 5, 9, 8, 6,
+	2, 2, 4, 6, 7, 11, 10, 8,
+	2, 4, 6, 6, 8, 10, 11, 9,
+	5, 6, 8, 9, 10, 12, 12, 10,
+	7, 9, 10, 10, 11, 10, 10, 10,
+];
+const NEXUS_6P_CHROMA: [u16; 64] = [
+	2, 2, 2, 5, 10, 10, 10, 10,
+	2, 2, 3, 7, 10, 10, 10, 10,
+	2, 3, 6, 10, 10, 10, 10, 10,
+	5, 7, 10, 10, 10, 10, 10, 10,
+	10, 10, 10, 10, 10, 10, 10, 10,
+	10, 10, 10, 10, 10, 10, 10, 10,
+	10, 10, 10, 10, 10, 10, 10, 10,
+	10, 10, 10, 10, 10, 10, 10, 10,
+];
+
 impl Modifier for Jpeg {
 	fn apply(&self, image: &mut Image) -> Result<()> {
-		let quality = self.quality()? as u8;
+		let settings = JpegSettings {
+			subsampling: sampling_factor(self.subsampling()?),
+			progressive: self.progressive()?,
+			quantization: self.quantization()?,
+			jitter: [random_jitter(), random_jitter()],
+		};
 
-		let mut buffer = Vec::new();
-		let mut cursor = Cursor::new(&mut buffer);
+		let color = image.color();
+		let opaque = remove_alpha(image.source());
+		let target = target_size(self.target_bpp()?, self.target_size()?, image);
+
+		let (quality, buffer) = match target {
+			Some(target) => search_quality(target, |quality| encode_jpeg(&opaque, quality, &settings))?,
+			None => {
+				let quality = self.quality()? as u8;
+				(quality, encode_jpeg(&opaque, quality, &settings)?)
+			}
+		};
 
-		let encoder = JpegEncoder::new_with_quality(&mut cursor, quality);
-		image.source_mut().write_with_encoder(encoder)?;
+		image.record(self.id(), "quality", quality)?;
 
-		cursor.set_position(0);
-		let decoder = JpegDecoder::new(&mut cursor)?;
-		*image.source_mut() = DynamicImage::from_decoder(decoder)?;
+		// Alpha is not part of the codec and passes through unchanged
+		let decoder = JpegDecoder::new(Cursor::new(&buffer))?;
+		*image.source_mut() = restore_alpha(&DynamicImage::from_decoder(decoder)?, image.source(), color);
 
 		Ok(())
 	}
@@ -36,17 +164,192 @@ impl Modifier for Jpeg {
 
 impl Modifier for WebP {
 	fn apply(&self, image: &mut Image) -> Result<()> {
-		let quality = self.quality()? as u8;
+		let settings = WebPSettings {
+			lossless: self.lossless()?,
+			method: self.method()?,
+			alpha_quality: self.alpha_quality()?,
+			near_lossless: self.near_lossless()?,
+			sharp_yuv: self.sharp_yuv()?,
+		};
+
+		let color = image.color();
+		let target = target_size(self.target_bpp()?, self.target_size()?, image);
+
+		if settings.lossless && target.is_some() {
+			return Err(anyhow!("lossless webp has no size target"));
+		}
+
+		let (quality, buffer) = match target {
+			Some(target) => search_quality(target, |quality| encode_webp(image.source(), quality, &settings))?,
+			None => {
+				let quality = self.quality()? as u8;
+				(quality, encode_webp(image.source(), quality, &settings)?)
+			}
+		};
+
+		image.record(self.id(), "quality", quality)?;
+
+		let decoder = WebPDecoder::new(Cursor::new(&buffer))?;
+		*image.source_mut() = restore_layout(DynamicImage::from_decoder(decoder)?, color);
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Qoi {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let color = image.color();
+
+		// QOI only stores 8-bit channels, anything deeper is reduced before encoding
+		if color.bytes_per_pixel() > color.channel_count() {
+			image.record(self.id(), "depth", 8)?;
+		}
+
+		let source = if color.has_alpha() {
+			ImageRgba8(image.source().to_rgba8())
+		} else {
+			ImageRgb8(image.source().to_rgb8())
+		};
 
 		let mut buffer = Vec::new();
-		let mut cursor = Cursor::new(&mut buffer);
+		source.write_with_encoder(QoiEncoder::new(&mut buffer))?;
+
+		let decoder = QoiDecoder::new(Cursor::new(&buffer))?;
+		*image.source_mut() = restore_layout(DynamicImage::from_decoder(decoder)?, color);
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for GenerationLoss {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let rounds = self.rounds()?;
+		let (width, height) = (image.width(), image.height());
+		let mut rng = thread_rng();
+
+		let mut codecs = Vec::new();
+		let mut scales = Vec::new();
+		let mut filters = Vec::new();
+		let mut offsets = Vec::new();
+
+		for round in 0..rounds {
+			// The first generation is the plain encode
+			let (scale, shift) = match round {
+				0 => (None, None),
+				_ => (self.rescale()?, self.shift()?),
+			};
+
+			// Padding moves the content against the block grid of the codec
+			let (x, y) = match shift {
+				Some(shift) => (rng.gen_range(0..=shift), rng.gen_range(0..=shift)),
+				None => (0, 0),
+			};
+
+			if x > 0 || y > 0 {
+				*image.source_mut() = pad_image(image.source(), [y, 0, x, 0], Border::Edge, 0.0)?;
+			}
+
+			let (padded_width, padded_height) = (image.width(), image.height());
+
+			// Both passes of a round use the same filter
+			let filter = match scale {
+				Some(_) => Some(self.filter()?),
+				None => None,
+			};
+
+			if let (Some(scale), Some(filter)) = (scale, filter) {
+				let scaled_width = ((padded_width as f32 * scale).round() as u32).max(1);
+				let scaled_height = ((padded_height as f32 * scale).round() as u32).max(1);
+
+				*image.source_mut() = resize_image(image.source(), scaled_width, scaled_height, filter.resize_type())?;
+			}
+
+			let (encoded_width, encoded_height) = (image.width(), image.height());
+
+			let codec = self.codecs().choose(&mut rng).ok_or(anyhow!("codecs"))?.get();
+			codec.apply(image)?;
+
+			if image.width() != encoded_width || image.height() != encoded_height {
+				return Err(anyhow!("codec {} changed the image size", codec.id()));
+			}
+
+			// Every round ends at the original size so rescaling and shifting do not accumulate
+			if let Some(filter) = filter {
+				*image.source_mut() = resize_image(image.source(), padded_width, padded_height, filter.resize_type())?;
+			}
 
-		let encoder = WebPEncoder::new_with_quality(&mut cursor, WebPQuality::lossy(quality));
-		image.source_mut().write_with_encoder(encoder)?;
+			if x > 0 || y > 0 {
+				*image.source_mut() = image.source().crop_imm(x, y, width, height);
+			}
 
-		cursor.set_position(0);
-		let decoder = WebPDecoder::new(&mut cursor)?;
-		*image.source_mut() = DynamicImage::from_decoder(decoder)?;
+			codecs.push(codec.id().to_owned());
+			scales.push(scale.unwrap_or(1.0));
+			filters.push(filter);
+			offsets.push([x, y]);
+		}
+
+		image.record(self.id(), "codecs", codecs)?;
+		image.record(self.id(), "scales", scales)?;
+		image.record(self.id(), "filters", filters)?;
+		image.record(self.id(), "offsets", offsets)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Palette {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let colors = self.colors()? as usize;
+		let method = self.method()?;
+		let dither = self.dither()?;
+		let codec = self.codec()?;
+
+		let color = image.color();
+		let mut rgba = image.source().to_rgba8();
+
+		if self.alpha()? == AlphaMode::Binarize {
+			for pixel in rgba.pixels_mut() {
+				pixel[3] = if pixel[3] < 128 { 0 } else { 255 };
+			}
+		}
+
+		let palette = match method {
+			PaletteMethod::MedianCut => median_cut(&rgba, colors),
+			PaletteMethod::NeuQuant => NeuQuant::new(10, colors, &rgba)
+				.color_map_rgba()
+				.chunks_exact(4)
+				.map(|color| [color[0], color[1], color[2], color[3]])
+				.collect(),
+		};
+
+		let indices = map_to_palette(&mut rgba, &palette, dither);
+
+		let rgba = match codec {
+			PaletteCodec::None => rgba,
+			PaletteCodec::Gif => {
+				let buffer = encode_gif(&rgba)?;
+				let decoder = GifDecoder::new(Cursor::new(&buffer))?;
+
+				DynamicImage::from_decoder(decoder)?.to_rgba8()
+			}
+			PaletteCodec::Png8 => {
+				let buffer = encode_png8(rgba.width(), rgba.height(), &palette, &indices)?;
+				let decoder = PngDecoder::new(Cursor::new(&buffer))?;
+
+				DynamicImage::from_decoder(decoder)?.to_rgba8()
+			}
+		};
+
+		*image.source_mut() = restore_layout(ImageRgba8(rgba), color);
 
 		Ok(())
 	}
@@ -54,3 +357,415 @@ impl Modifier for WebP {
 		self.id.as_ref()
 	}
 }
+
+pub struct JpegSettings {
+	pub subsampling: SamplingFactor,
+	pub progressive: bool,
+	pub quantization: QuantizationTable,
+	pub jitter: [[f32; 64]; 2],
+}
+
+pub fn encode_jpeg(source: &DynamicImage, quality: u8, settings: &JpegSettings) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+
+	let mut encoder = Encoder::new(&mut buffer, quality);
+	encoder.set_sampling_factor(settings.subsampling);
+	encoder.set_progressive(settings.progressive);
+
+	let (luma_table, chroma_table) = quantization_tables(settings, quality);
+	encoder.set_quantization_tables(luma_table, chroma_table);
+
+	let width = source.width().try_into()?;
+	let height = source.height().try_into()?;
+
+	// JPEG has no alpha channel, dropping it here would lose data silently
+	if source.color().has_alpha() {
+		return Err(anyhow!("jpeg can not encode an alpha channel"));
+	}
+
+	match source {
+		ImageLuma8(luma) => encoder.encode(luma, width, height, ColorType::Luma)?,
+		ImageRgb8(rgb) => encoder.encode(rgb, width, height, ColorType::Rgb)?,
+		_ if source.color().has_color() => encoder.encode(&source.to_rgb8(), width, height, ColorType::Rgb)?,
+		_ => encoder.encode(&source.to_luma8(), width, height, ColorType::Luma)?,
+	}
+
+	Ok(buffer)
+}
+
+pub struct WebPSettings {
+	pub lossless: bool,
+	pub method: u32,
+	pub alpha_quality: u32,
+	pub near_lossless: u32,
+	pub sharp_yuv: bool,
+}
+
+impl Default for WebPSettings {
+	fn default() -> Self {
+		WebPSettings { lossless: false, method: 4, alpha_quality: 100, near_lossless: 100, sharp_yuv: false }
+	}
+}
+
+/// Encodes with libwebp. In lossless mode `quality` is the compression effort.
+pub fn encode_webp(source: &DynamicImage, quality: u8, settings: &WebPSettings) -> Result<Vec<u8>> {
+	let mut config = WebPConfig::new().map_err(|_| anyhow!("webp config"))?;
+
+	config.lossless = settings.lossless as i32;
+	config.quality = quality as f32;
+	config.method = settings.method as i32;
+	config.alpha_quality = settings.alpha_quality as i32;
+	config.near_lossless = settings.near_lossless as i32;
+	config.use_sharp_yuv = settings.sharp_yuv as i32;
+	config.exact = settings.lossless as i32;
+
+	let width = source.width();
+	let height = source.height();
+
+	let memory = if source.color().has_alpha() {
+		let rgba = source.to_rgba8();
+		webp::Encoder::from_rgba(&rgba, width, height).encode_advanced(&config)
+	} else {
+		let rgb = source.to_rgb8();
+		webp::Encoder::from_rgb(&rgb, width, height).encode_advanced(&config)
+	};
+
+	let memory = memory.map_err(|err| anyhow!("webp encoding: {:?}", err))?;
+	Ok(memory.to_vec())
+}
+
+/// Size in bytes the encoded image should fit in, if any target is configured.
+fn target_size(bpp: Option<f32>, size: Option<u32>, image: &Image) -> Option<usize> {
+	let pixels = image.width() as f32 * image.height() as f32;
+
+	match (bpp, size) {
+		(Some(bpp), _) => Some((bpp * pixels / 8.0) as usize),
+		(None, Some(size)) => Some(size as usize),
+		(None, None) => None,
+	}
+}
+
+/// Binary searches the highest quality whose encoded size does not exceed `target`.
+/// Falls back to the lowest quality if nothing fits.
+fn search_quality<F>(target: usize, mut encode: F) -> Result<(u8, Vec<u8>)>
+where
+	F: FnMut(u8) -> Result<Vec<u8>>,
+{
+	let mut low = 1u8;
+	let mut high = 100u8;
+	let mut best = None;
+
+	while low <= high {
+		let quality = low + (high - low) / 2;
+		let buffer = encode(quality)?;
+
+		if buffer.len() <= target {
+			best = Some((quality, buffer));
+			low = quality + 1;
+		} else {
+			high = quality - 1;
+		}
+	}
+
+	match best {
+		Some(best) => Ok(best),
+		None => Ok((1, encode(1)?)),
+	}
+}
+
+pub fn sampling_factor(subsampling: ChromaSubsampling) -> SamplingFactor {
+	match subsampling {
+		ChromaSubsampling::Ratio444 => SamplingFactor::R_4_4_4,
+		ChromaSubsampling::Ratio440 => SamplingFactor::R_4_4_0,
+		ChromaSubsampling::Ratio422 => SamplingFactor::R_4_2_2,
+		ChromaSubsampling::Ratio420 => SamplingFactor::R_4_2_0,
+		ChromaSubsampling::Ratio411 => SamplingFactor::R_4_1_1,
+	}
+}
+
+fn quantization_tables(settings: &JpegSettings, quality: u8) -> (QuantizationTableType, QuantizationTableType) {
+	let table = match settings.quantization {
+		QuantizationTable::AnnexK => QuantizationTableType::Default,
+		QuantizationTable::Flat => QuantizationTableType::Flat,
+		QuantizationTable::MsSsim => QuantizationTableType::CustomMsSsim,
+		QuantizationTable::PsnrHvs => QuantizationTableType::CustomPsnrHvs,
+		QuantizationTable::ImageMagick => QuantizationTableType::ImageMagick,
+		QuantizationTable::KleinSilversteinCarney => QuantizationTableType::KleinSilversteinCarney,
+		QuantizationTable::DentalXRays => QuantizationTableType::DentalXRays,
+		QuantizationTable::VisualDetectionModel => QuantizationTableType::VisualDetectionModel,
+		QuantizationTable::ImprovedDetectionModel => QuantizationTableType::ImprovedDetectionModel,
+		QuantizationTable::PhotoshopLow => {
+			return (
+				scaled_quantization_table(&PHOTOSHOP_LOW_LUMA, quality),
+				scaled_quantization_table(&PHOTOSHOP_LOW_CHROMA, quality),
+			);
+		}
+		QuantizationTable::PhotoshopMaximum => {
+			return (
+				scaled_quantization_table(&PHOTOSHOP_MAXIMUM_LUMA, quality),
+				scaled_quantization_table(&PHOTOSHOP_MAXIMUM_CHROMA, quality),
+			);
+		}
+		QuantizationTable::Nexus6P => {
+			return (
+				scaled_quantization_table(&NEXUS_6P_LUMA, quality),
+				scaled_quantization_table(&NEXUS_6P_CHROMA, quality),
+			);
+		}
+		QuantizationTable::Random => {
+			let [luma_jitter, chroma_jitter] = &settings.jitter;
+
+			let luma = jittered_quantization_table(&ANNEX_K_LUMA, luma_jitter, quality);
+			let chroma = jittered_quantization_table(&ANNEX_K_CHROMA, chroma_jitter, quality);
+
+			return (luma, chroma);
+		}
+	};
+
+	(table.clone(), table)
+}
+
+/// Per-coefficient multipliers for a random quantization table.
+fn random_jitter() -> [f32; 64] {
+	let mut rng = thread_rng();
+	let mut jitter = [0f32; 64];
+
+	for value in jitter.iter_mut() {
+		*value = rng.gen_range(-0.5f32..0.5).exp();
+	}
+
+	jitter
+}
+
+/// Scales a captured table like libjpeg does for `quality`, quality 50 keeps the table as it was read.
+fn scaled_quantization_table(base: &[u16; 64], quality: u8) -> QuantizationTableType {
+	jittered_quantization_table(base, &[1.0; 64], quality)
+}
+
+/// Jitters every coefficient of the base table and scales the result like libjpeg does for `quality`.
+fn jittered_quantization_table(base: &[u16; 64], jitter: &[f32; 64], quality: u8) -> QuantizationTableType {
+	let quality = quality as f32;
+	let scale = if quality < 50.0 { 5000.0 / quality } else { 200.0 - quality * 2.0 };
+
+	let mut table = [0u16; 64];
+
+	for ((value, base), jitter) in table.iter_mut().zip(base.iter()).zip(jitter.iter()) {
+		let scaled = (*base as f32 * jitter * scale + 50.0) / 100.0;
+
+		*value = scaled.round().clamp(1.0, 255.0) as u16;
+	}
+
+	QuantizationTableType::Custom(Box::new(table))
+}
+
+/// Median cut palette over the RGBA pixels of the image.
+fn median_cut(image: &RgbaImage, colors: usize) -> Vec<[u8; 4]> {
+	let mut boxes = vec![image.pixels().map(|pixel| pixel.0).collect::<Vec<_>>()];
+
+	while boxes.len() < colors {
+		let widest = boxes
+			.iter()
+			.enumerate()
+			.map(|(index, pixels)| {
+				let (channel, range) = widest_channel(pixels);
+				(index, channel, range)
+			})
+			.max_by_key(|(_, _, range)| *range);
+
+		let (index, channel) = match widest {
+			Some((index, channel, range)) if range > 0 => (index, channel),
+			_ => break,
+		};
+
+		let mut pixels = boxes.swap_remove(index);
+		pixels.sort_unstable_by_key(|pixel| pixel[channel]);
+
+		// Cutting between equal values would put the same color into both halves
+		let median = pixels[pixels.len() / 2][channel];
+		let split = match pixels.partition_point(|pixel| pixel[channel] < median) {
+			0 => pixels.partition_point(|pixel| pixel[channel] <= median),
+			split => split,
+		};
+
+		let upper = pixels.split_off(split);
+
+		boxes.push(pixels);
+		boxes.push(upper);
+	}
+
+	boxes
+		.iter()
+		.filter(|pixels| !pixels.is_empty())
+		.map(|pixels| {
+			let mut sum = [0u64; 4];
+
+			for pixel in pixels {
+				for (sum, channel) in sum.iter_mut().zip(pixel.iter()) {
+					*sum += *channel as u64;
+				}
+			}
+
+			sum.map(|sum| (sum / pixels.len() as u64) as u8)
+		})
+		.collect()
+}
+
+fn widest_channel(pixels: &[[u8; 4]]) -> (usize, u8) {
+	let mut min = [u8::MAX; 4];
+	let mut max = [u8::MIN; 4];
+
+	for pixel in pixels {
+		for channel in 0..4 {
+			min[channel] = min[channel].min(pixel[channel]);
+			max[channel] = max[channel].max(pixel[channel]);
+		}
+	}
+
+	(0..4)
+		.map(|channel| (channel, max[channel].saturating_sub(min[channel])))
+		.max_by_key(|(_, range)| *range)
+		.unwrap_or((0, 0))
+}
+
+fn nearest_color(palette: &[[u8; 4]], target: &[f32; 4]) -> usize {
+	let distance = |color: &[u8; 4]| -> f32 {
+		color
+			.iter()
+			.zip(target.iter())
+			.map(|(color, target)| (*color as f32 - target).powi(2))
+			.sum()
+	};
+
+	palette
+		.iter()
+		.enumerate()
+		.min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+		.map(|(index, _)| index)
+		.unwrap_or(0)
+}
+
+/// Replaces every pixel with its palette color and returns the palette indices.
+/// Dithering only spreads the error of the color channels, alpha is matched as is.
+fn map_to_palette(image: &mut RgbaImage, palette: &[[u8; 4]], dither: Dither) -> Vec<u8> {
+	let (width, height) = image.dimensions();
+	let spread = 255.0 / (palette.len() as f32).cbrt();
+
+	let mut error = vec![[0f32; 3]; (width * height) as usize];
+	let mut indices = Vec::with_capacity((width * height) as usize);
+
+	for y in 0..height {
+		for x in 0..width {
+			let pixel = image.get_pixel_mut(x, y);
+			let mut target = pixel.0.map(|channel| channel as f32);
+
+			let offset = match dither {
+				Dither::None => [0.0; 3],
+				Dither::Bayer => {
+					let threshold = (BAYER_8X8[(y % 8) as usize][(x % 8) as usize] as f32 + 0.5) / 64.0 - 0.5;
+					[threshold * spread; 3]
+				}
+				Dither::FloydSteinberg => error[(y * width + x) as usize],
+			};
+
+			for (channel, offset) in target.iter_mut().zip(offset.iter()) {
+				*channel += offset;
+			}
+
+			let index = nearest_color(palette, &target);
+			let color = palette[index];
+
+			if dither == Dither::FloydSteinberg {
+				let mut diffuse = |dx: i64, dy: i64, weight: f32| {
+					let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+
+					if nx >= 0 && nx < width as i64 && ny < height as i64 {
+						let error = &mut error[(ny * width as i64 + nx) as usize];
+
+						for channel in 0..3 {
+							error[channel] += (target[channel] - color[channel] as f32) * weight;
+						}
+					}
+				};
+
+				diffuse(1, 0, 7.0 / 16.0);
+				diffuse(-1, 1, 3.0 / 16.0);
+				diffuse(0, 1, 5.0 / 16.0);
+				diffuse(1, 1, 1.0 / 16.0);
+			}
+
+			*pixel = Rgba(color);
+			indices.push(index as u8);
+		}
+	}
+
+	indices
+}
+
+fn encode_gif(image: &RgbaImage) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+
+	let mut encoder = GifEncoder::new(&mut buffer);
+	encoder.encode(image, image.width(), image.height(), image::ColorType::Rgba8)?;
+	drop(encoder);
+
+	Ok(buffer)
+}
+
+fn encode_png8(width: u32, height: u32, palette: &[[u8; 4]], indices: &[u8]) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+
+	let mut encoder = png::Encoder::new(&mut buffer, width, height);
+	encoder.set_color(png::ColorType::Indexed);
+	encoder.set_depth(png::BitDepth::Eight);
+	encoder.set_palette(palette.iter().flat_map(|color| [color[0], color[1], color[2]]).collect::<Vec<_>>());
+
+	if palette.iter().any(|color| color[3] < u8::MAX) {
+		encoder.set_trns(palette.iter().map(|color| color[3]).collect::<Vec<_>>());
+	}
+
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(indices)?;
+	writer.finish()?;
+
+	Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn search_quality_falls_back_to_lowest_quality() {
+		let encode = |quality: u8| Ok(vec![0; 100 + quality as usize * 10]);
+
+		let (quality, buffer) = search_quality(50, encode).unwrap();
+		assert_eq!((quality, buffer.len()), (1, 110));
+
+		let (quality, buffer) = search_quality(555, encode).unwrap();
+		assert_eq!((quality, buffer.len()), (45, 550));
+	}
+
+	#[test]
+	fn palette_keeps_images_with_fewer_colors() {
+		let colors = [[200, 30, 30, 255], [30, 200, 30, 255], [30, 30, 200, 128]];
+		let image = RgbaImage::from_fn(12, 9, |x, y| Rgba(colors[((x + y) % 3) as usize]));
+
+		let mut palette = median_cut(&image, 16);
+		palette.sort_unstable();
+
+		let mut expected = colors.to_vec();
+		expected.sort_unstable();
+		assert_eq!(palette, expected);
+
+		// Extra entries must not pull pixels away from their exact match
+		palette.extend([[0, 0, 0, 255], [255, 255, 255, 255], [120, 120, 120, 255]]);
+
+		for dither in [Dither::None, Dither::FloydSteinberg] {
+			let mut mapped = image.clone();
+			let indices = map_to_palette(&mut mapped, &palette, dither);
+
+			assert_eq!(mapped, image, "{:?}", dither);
+			assert!(indices.iter().zip(image.pixels()).all(|(index, pixel)| palette[*index as usize] == pixel.0));
+		}
+	}
+}
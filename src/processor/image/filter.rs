@@ -1,11 +1,22 @@
 use crate::config::filter::*;
+use crate::config::enumerations::PlaneGroup;
+use super::operation::resize_image;
 use super::Image;
 use super::Modifier;
 
 use anyhow::Result;
+use anyhow::anyhow;
 
 use imageproc::filter::*;
+use imageproc::definitions::Clamp;
 use image::DynamicImage::*;
+use image::ImageBuffer;
+use image::Pixel;
+
+use conv::ValueInto;
+use std::f64::consts::PI;
+use std::f64::consts::FRAC_PI_4;
+use std::f64::consts::FRAC_2_PI;
 
 impl Modifier for GaussianBlur {
 	fn apply(&self, image: &mut Image) -> Result<()> {
@@ -46,7 +57,7 @@ impl Modifier for BilateralFilter {
 			filtered_channels.push(filtered);
 		}
 
-		let combined = image.combine_channels(filtered_channels);
+		let combined = image.combine_channels(filtered_channels)?;
 		*image.source_mut() = combined;
 
 		Ok(())
@@ -69,7 +80,7 @@ impl Modifier for BoxFilter {
 			filtered_channels.push(filtered);
 		}
 
-		let combined = image.combine_channels(filtered_channels);
+		let combined = image.combine_channels(filtered_channels)?;
 		*image.source_mut() = combined;
 
 		Ok(())
@@ -89,7 +100,7 @@ impl Modifier for Sharpen3x3 {
 			filtered_channels.push(filtered);
 		}
 
-		let combined = image.combine_channels(filtered_channels);
+		let combined = image.combine_channels(filtered_channels)?;
 		*image.source_mut() = combined;
 
 		Ok(())
@@ -112,7 +123,7 @@ impl Modifier for SharpenGaussian {
 			filtered_channels.push(filtered);
 		}
 
-		let combined = image.combine_channels(filtered_channels);
+		let combined = image.combine_channels(filtered_channels)?;
 		*image.source_mut() = combined;
 
 		Ok(())
@@ -141,3 +152,145 @@ impl Modifier for MedianFilter {
 		self.id.as_ref()
 	}
 }
+
+impl Modifier for SincFilter {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let cutoff = self.cutoff()?;
+		let size = self.kernel_size()? | 1;
+
+		let kernel = sinc_kernel(cutoff, size);
+		let kernel = Kernel::new(&kernel, size, size);
+
+		*image.source_mut() = match image.source() {
+			ImageLuma8(image) => ImageLuma8(sinc_filter(image, &kernel)),
+			ImageLuma16(image) => ImageLuma16(sinc_filter(image, &kernel)),
+			ImageLumaA8(image) => ImageLumaA8(sinc_filter(image, &kernel)),
+			ImageLumaA16(image) => ImageLumaA16(sinc_filter(image, &kernel)),
+			ImageRgb8(image) => ImageRgb8(sinc_filter(image, &kernel)),
+			ImageRgb16(image) => ImageRgb16(sinc_filter(image, &kernel)),
+			ImageRgb32F(image) => ImageRgb32F(sinc_filter(image, &kernel)),
+			ImageRgba8(image) => ImageRgba8(sinc_filter(image, &kernel)),
+			ImageRgba16(image) => ImageRgba16(sinc_filter(image, &kernel)),
+			ImageRgba32F(image) => ImageRgba32F(sinc_filter(image, &kernel)),
+			_ => return Err(anyhow!("unsupported color")),
+		};
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for ChromaSubsample {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let space = self.space()?;
+		let (fx, fy) = self.subsampling()?.factors();
+		let downsample = self.downsample()?;
+		let upsample = self.upsample()?;
+
+		let width = image.width();
+		let height = image.height();
+
+		let mut planes = image.separate_planes(space)?;
+
+		for plane in planes[1..3].iter_mut() {
+			*plane = resize_image(plane, width.div_ceil(fx), height.div_ceil(fy), downsample.resize_type())?;
+		}
+
+		if let Some(degradation) = self.degradation() {
+			let modifier = degradation.get();
+			let target = match self.target()? {
+				PlaneGroup::Luma => 0..1,
+				PlaneGroup::Chroma => 1..3,
+			};
+
+			planes = planes
+				.into_iter()
+				.enumerate()
+				.map(|(index, plane)| {
+					if target.contains(&index) {
+						image.apply_to_plane(modifier.as_ref(), plane)
+					} else {
+						Ok(plane)
+					}
+				})
+				.collect::<Result<_>>()?;
+		}
+
+		for plane in planes[1..3].iter_mut() {
+			*plane = resize_image(plane, width, height, upsample.resize_type())?;
+		}
+
+		*image.source_mut() = image.combine_planes(space, planes)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Convolves every channel with the kernel, clamping the result to the subpixel range.
+fn sinc_filter<P>(image: &ImageBuffer<P, Vec<P::Subpixel>>, kernel: &Kernel<f32>) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+	P: Pixel,
+	P::Subpixel: ValueInto<f32> + Clamp<f32>,
+{
+	kernel.filter(image, |channel, value| *channel = <P::Subpixel as Clamp<f32>>::clamp(value))
+}
+
+/// Circular low-pass (2D sinc) kernel, as used by the Real-ESRGAN degradation model.
+/// `cutoff` is the cutoff frequency in radians, `size` must be odd.
+fn sinc_kernel(cutoff: f32, size: u32) -> Vec<f32> {
+	let cutoff = cutoff as f64;
+	let center = (size / 2) as f64;
+
+	let mut kernel = Vec::with_capacity((size * size) as usize);
+
+	for y in 0..size {
+		for x in 0..size {
+			let dx = x as f64 - center;
+			let dy = y as f64 - center;
+			let r = (dx * dx + dy * dy).sqrt();
+
+			let value = if r < f64::EPSILON {
+				cutoff * cutoff / (4.0 * PI)
+			} else {
+				cutoff * bessel_j1(cutoff * r) / (2.0 * PI * r)
+			};
+
+			kernel.push(value);
+		}
+	}
+
+	let sum: f64 = kernel.iter().sum();
+	kernel.iter().map(|value| (value / sum) as f32).collect()
+}
+
+/// Bessel function of the first kind of order one (rational approximation).
+fn bessel_j1(x: f64) -> f64 {
+	let ax = x.abs();
+
+	if ax < 8.0 {
+		let y = x * x;
+		let num = x * (72362614232.0 + y * (-7895059235.0 + y * (242396853.1
+			+ y * (-2972611.439 + y * (15704.48260 + y * -30.16036606)))));
+		let den = 144725228442.0 + y * (2300535178.0 + y * (18583304.74
+			+ y * (99447.43394 + y * (376.9991397 + y))));
+
+		num / den
+	} else {
+		let z = 8.0 / ax;
+		let y = z * z;
+		let xx = ax - 3.0 * FRAC_PI_4;
+
+		let p = 1.0 + y * (0.183105e-2 + y * (-0.3516396496e-4
+			+ y * (0.2457520174e-5 + y * -0.240337019e-6)));
+		let q = 0.04687499995 + y * (-0.2002690873e-3 + y * (0.8449199096e-5
+			+ y * (-0.88228987e-6 + y * 0.105787412e-6)));
+
+		let value = (FRAC_2_PI / ax).sqrt() * (xx.cos() * p - z * xx.sin() * q);
+		if x < 0.0 { -value } else { value }
+	}
+}
@@ -1,35 +1,53 @@
-use image::RgbImage;
 use image::GrayImage;
-use image::RgbaImage;
-use image::GrayAlphaImage;
 use image::DynamicImage;
 
 use image::ColorType;
-use image::ColorType::*;
 use image::ImageFormat;
 use image::io::Reader;
 
-use image::Pixel;
-use image::Luma;
-use image::LumaA;
-use image::Rgb;
-use image::Rgba;
-
 use std::io::prelude::*;
 use std::io::Cursor;
 use std::fs::File;
 use std::path::Path;
 
-use std::ffi::OsStr;
 use std::ffi::OsString;
+use std::collections::HashMap;
 
 use anyhow::Result;
 use anyhow::anyhow;
 
+use crate::config::Output;
+use crate::config::enumerations::BitDepth;
+use crate::config::enumerations::ColorSpace;
+use crate::config::enumerations::SaveFormat;
+use crate::config::enumerations::QuantizationTable;
+use crate::config::enumerations::PngCompression;
+use crate::config::enumerations::PngFilter;
+use self::compression::JpegSettings;
+use self::compression::WebPSettings;
+use self::compression::encode_jpeg;
+use self::compression::encode_webp;
+use self::compression::sampling_factor;
+
+use image::codecs::png::PngEncoder;
+use image::codecs::png::CompressionType;
+use image::codecs::png::FilterType;
+use image::DynamicImage::*;
+use std::borrow::Cow;
+use std::io::BufWriter;
+
+use serde::Serialize;
+use serde_yaml::Value;
+
 pub mod filter;
 pub mod compression;
 pub mod adjustment;
 pub mod operation;
+pub mod camera;
+pub mod analog;
+pub mod print;
+pub mod weather;
+pub mod planes;
 
 pub trait Modifier {
 	fn apply(&self, image: &mut Image) -> Result<()>;
@@ -38,11 +56,19 @@ pub trait Modifier {
 
 pub struct Image {
 	source: DynamicImage,
-	color: ColorType,
 	filename: OsString,
 	format: ImageFormat,
-	width: u32,
-	height: u32,
+	records: Vec<Record>,
+	marks: HashMap<String, (u32, u32)>,
+}
+
+/// Value resolved by a modifier while processing an image, written to the manifest.
+#[derive(Debug, Clone)]
+#[derive(Serialize)]
+pub struct Record {
+	id: String,
+	name: String,
+	value: Value,
 }
 
 impl Image {
@@ -58,198 +84,111 @@ impl Image {
 		let format = reader.format().ok_or(anyhow!("failed to get image format"))?;
 		let image = reader.decode()?;
 
-		let color = image.color();
-		let width = image.width();
-		let height = image.height();
-
-		let image = Image { source: image, color, filename, format, width, height };
+		let image = Image { source: image, filename, format, records: Vec::new(), marks: HashMap::new() };
 
 		Ok(image)
 	}
-	fn separate_luma(&self) -> Result<Vec<GrayImage>> {
-		let luma = self.source.as_luma8();
-
-		if let Some(luma) = luma {
-			Ok(vec![luma.clone()])
-		} else {
-			Err(anyhow!("image is not luma"))
-		}
-	}
-	fn separate_luma_alpha(&self) -> Result<Vec<GrayImage>> {
-		let luma_alpha = self.source.as_luma_alpha8();
-
-		if let Some(luma_alpha) = luma_alpha {
-			let width = self.width;
-			let height = self.height;
-
-			let mut luma = GrayImage::new(width, height);
-			let mut alpha = GrayImage::new(width, height);
-
-			for (x, y, pixel) in luma_alpha.enumerate_pixels() {
-				let channels = pixel.channels();
-
-				let luma_pix = Luma([channels[0]]);
-				let alpha_pix = Luma([channels[1]]);
-
-				luma.put_pixel(x, y, luma_pix);
-				alpha.put_pixel(x, y, alpha_pix);
-			}
-
-			Ok(vec![luma, alpha])
-		} else {
-			Err(anyhow!("image is not luma alpha"))
-		}
-	}
-	fn separate_rgb(&self) -> Result<Vec<GrayImage>> {
-		let rgb = self.source.as_rgb8();
-
-		if let Some(rgb) = rgb {
-			let width = self.width;
-			let height = self.height;
-
-			let mut red = GrayImage::new(width, height);
-			let mut green = GrayImage::new(width, height);
-			let mut blue = GrayImage::new(width, height);
-
-			for (x, y, pixel) in rgb.enumerate_pixels() {
-				let channels = pixel.channels();
-
-				let red_pix = Luma([channels[0]]);
-				let green_pix = Luma([channels[1]]);
-				let blue_pix = Luma([channels[2]]);
-
-				red.put_pixel(x, y, red_pix);
-				green.put_pixel(x, y, green_pix);
-				blue.put_pixel(x, y, blue_pix);
-			}
-
-			Ok(vec![red, green, blue])
-		} else {
-			Err(anyhow!("image is not rgb8"))
-		}
-	}
-	fn separate_rgba(&self) -> Result<Vec<GrayImage>> {
-		let rgba = self.source.as_rgba8();
-
-		if let Some(rgba) = rgba {
-			let width = self.width;
-			let height = self.height;
-
-			let mut red = GrayImage::new(width, height);
-			let mut green = GrayImage::new(width, height);
-			let mut blue = GrayImage::new(width, height);
-			let mut alpha = GrayImage::new(width, height);
-
-			for (x, y, pixel) in rgba.enumerate_pixels() {
-				let channels = pixel.channels();
-
-				let red_pix = Luma([channels[0]]);
-				let green_pix = Luma([channels[1]]);
-				let blue_pix = Luma([channels[2]]);
-				let alpha_pix = Luma([channels[3]]);
-
-				red.put_pixel(x, y, red_pix);
-				green.put_pixel(x, y, green_pix);
-				blue.put_pixel(x, y, blue_pix);
-				alpha.put_pixel(x, y, alpha_pix);
-			}
-
-			Ok(vec![red, green, blue, alpha])
-		} else {
-			Err(anyhow!("image is not rgba8"))
-		}
-	}
+	/// Splits an 8-bit image into its native channels, luma or RGB followed by alpha.
 	pub fn separate_channels(&self) -> Result<Vec<GrayImage>> {
-		match self.color {
-			L8 => self.separate_luma(),
-			La8 => self.separate_luma_alpha(),
-			Rgb8 => self.separate_rgb(),
-			Rgba8 => self.separate_rgba(),
-			_ => unimplemented!(),
-		}
-	}
-	fn combine_luma(&self, channels: Vec<GrayImage>) -> DynamicImage {
-		DynamicImage::ImageLuma8(channels[0].clone())
-	}
-	fn combine_luma_alpha(&self, channels: Vec<GrayImage>) -> DynamicImage {
-		let width = self.width;
-		let height = self.height;
-
-		let mut luma_alpha = GrayAlphaImage::new(width, height);
+		let color = self.color();
 
-		let luma = &channels[0];
-		let alpha = &channels[1];
+		if color.bytes_per_pixel() != color.channel_count() {
+			return Err(anyhow!("unsupported color"));
+		}
 
-		for (x, y, pixel) in luma_alpha.enumerate_pixels_mut() {
-			let luma_pix = luma.get_pixel(x, y).channels();
-			let alpha_pix = alpha.get_pixel(x, y).channels();
+		let mut planes = self.separate_planes(ColorSpace::Rgb)?;
 
-			let luma_alpha_pix = LumaA([luma_pix[0], alpha_pix[0]]);
-			*pixel = luma_alpha_pix;
+		if !color.has_color() {
+			planes.drain(1..3);
 		}
 
-		DynamicImage::ImageLumaA8(luma_alpha)
+		Ok(planes.into_iter().map(|plane| plane.into_luma8()).collect())
 	}
-	fn combine_rgb(&self, channels: Vec<GrayImage>) -> DynamicImage {
-		let width = self.width;
-		let height = self.height;
-
-		let mut rgb = RgbImage::new(width, height);
+	/// Inverse of [`Image::separate_channels`].
+	pub fn combine_channels(&self, channels: Vec<GrayImage>) -> Result<DynamicImage> {
+		let mut planes: Vec<DynamicImage> = channels.into_iter().map(ImageLuma8).collect();
 
-		let red = &channels[0];
-		let green = &channels[1];
-		let blue = &channels[2];
-
-		for (x, y, pixel) in rgb.enumerate_pixels_mut() {
-			let red_pix = red.get_pixel(x, y).channels();
-			let green_pix = green.get_pixel(x, y).channels();
-			let blue_pix = blue.get_pixel(x, y).channels();
-
-			let rgb_pix = Rgb([red_pix[0], green_pix[0], blue_pix[0]]);
-			*pixel = rgb_pix;
+		if !self.color().has_color() && !planes.is_empty() {
+			let luma = planes[0].clone();
+			planes.splice(1..1, [luma.clone(), luma]);
 		}
 
-		DynamicImage::ImageRgb8(rgb)
+		self.combine_planes(ColorSpace::Rgb, planes)
 	}
-	fn combine_rgba(&self, channels: Vec<GrayImage>) -> DynamicImage {
-		let width = self.width;
-		let height = self.height;
-
-		let mut rgba = RgbaImage::new(width, height);
-
-		let red = &channels[0];
-		let green = &channels[1];
-		let blue = &channels[2];
-		let alpha = &channels[3];
-
-		for (x, y, pixel) in rgba.enumerate_pixels_mut() {
-			let red_pix = red.get_pixel(x, y).channels();
-			let green_pix = green.get_pixel(x, y).channels();
-			let blue_pix = blue.get_pixel(x, y).channels();
-			let alpha_pix = alpha.get_pixel(x, y).channels();
+	pub fn save<P>(&self, path: P, output: &Output) -> Result<()> where P: AsRef<Path> {
+		let path = path.as_ref().join(&self.filename);
+		let source = convert_depth(&self.source, output.depth());
+
+		let (format, path) = match output.format() {
+			SaveFormat::Original => match self.format {
+				ImageFormat::Png => (SaveFormat::Png, path),
+				ImageFormat::Jpeg => (SaveFormat::Jpeg, path),
+				ImageFormat::WebP => (SaveFormat::Webp, path),
+				_ => return Ok(source.save(path)?),
+			},
+			SaveFormat::Png => (SaveFormat::Png, path.with_extension("png")),
+			SaveFormat::Jpeg => (SaveFormat::Jpeg, path.with_extension("jpg")),
+			SaveFormat::Webp => (SaveFormat::Webp, path.with_extension("webp")),
+		};
+
+		let mut writer = BufWriter::new(File::create(path)?);
+
+		match format {
+			SaveFormat::Png => {
+				let options = output.png();
+				let encoder = PngEncoder::new_with_quality(&mut writer, png_compression(options.compression()), png_filter(options.filter()));
+
+				source.write_with_encoder(encoder)?;
+			}
+			SaveFormat::Jpeg => {
+				let options = output.jpeg();
+				let settings = JpegSettings {
+					subsampling: sampling_factor(options.subsampling()),
+					progressive: false,
+					quantization: QuantizationTable::AnnexK,
+					jitter: [[1.0; 64]; 2],
+				};
+
+				let source = match (source.color().has_alpha(), options.background()) {
+					(false, _) => source,
+					(true, Some(background)) => Cow::Owned(flatten(&source, background)),
+					(true, None) => return Err(anyhow!("jpeg output of {} has alpha but no background is set", self.filename())),
+				};
+
+				writer.write_all(&encode_jpeg(&source, options.quality(), &settings)?)?;
+			}
+			SaveFormat::Webp => {
+				let options = output.webp();
+				let settings = WebPSettings { lossless: options.lossless(), ..Default::default() };
 
-			let rgba_pix = Rgba([red_pix[0], green_pix[0], blue_pix[0], alpha_pix[0]]);
-			*pixel = rgba_pix;
+				writer.write_all(&encode_webp(&source, options.quality(), &settings)?)?;
+			}
+			SaveFormat::Original => return Err(anyhow!("save format of {} is not resolved", self.filename())),
 		}
 
-		DynamicImage::ImageRgba8(rgba)
+		writer.flush()?;
+		Ok(())
 	}
-	pub fn combine_channels(&self, channels: Vec<GrayImage>) -> DynamicImage {
-		match self.color {
-			L8 => self.combine_luma(channels),
-			La8 => self.combine_luma_alpha(channels),
-			Rgb8 => self.combine_rgb(channels),
-			Rgba8 => self.combine_rgba(channels),
-			_ => unimplemented!(),
-		}
+	/// New image with the same file information and the given source.
+	fn derive(&self, source: DynamicImage) -> Image {
+		Image { source, filename: self.filename.clone(), format: self.format, records: Vec::new(), marks: HashMap::new() }
 	}
-	pub fn save<P>(&self, path: P) -> Result<()> where P: AsRef<Path> {
-		let path = path.as_ref();
-		let filename: &OsStr = self.filename.as_ref();
+	pub fn record<V>(&mut self, id: &str, name: &str, value: V) -> Result<()> where V: Serialize {
+		let value = serde_yaml::to_value(value)?;
 
-		self.source.save(path.join(filename))?;
+		self.records.push(Record { id: id.to_owned(), name: name.to_owned(), value });
 		Ok(())
 	}
+	/// Remembers the current size under `name`.
+	pub fn mark_size(&mut self, name: &str) {
+		self.marks.insert(name.to_owned(), (self.width(), self.height()));
+	}
+	pub fn marked_size(&self, name: &str) -> Option<(u32, u32)> {
+		self.marks.get(name).copied()
+	}
+	pub fn records(&self) -> &Vec<Record> {
+		&self.records
+	}
 	pub fn source(&self) -> &DynamicImage {
 		&self.source
 	}
@@ -260,15 +199,129 @@ impl Image {
 		self.filename.to_str().unwrap()
 	}
 	pub fn width(&self) -> u32 {
-		self.width
+		self.source.width()
 	}
 	pub fn height(&self) -> u32 {
-		self.height
+		self.source.height()
 	}
 	pub fn format(&self) -> ImageFormat {
 		self.format
 	}
 	pub fn color(&self) -> ColorType {
-		self.color
+		self.source.color()
+	}
+}
+
+// Ordered dithering thresholds in 0..64.
+pub(crate) const BAYER_8X8: [[u8; 8]; 8] = [
+	[0, 32, 8, 40, 2, 34, 10, 42],
+	[48, 16, 56, 24, 50, 18, 58, 26],
+	[12, 44, 4, 36, 14, 46, 6, 38],
+	[60, 28, 52, 20, 62, 30, 54, 22],
+	[3, 35, 11, 43, 1, 33, 9, 41],
+	[51, 19, 59, 27, 49, 17, 57, 25],
+	[15, 47, 7, 39, 13, 45, 5, 37],
+	[63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Converts an 8-bit image to the channel layout of `color`.
+pub fn restore_layout(image: DynamicImage, color: ColorType) -> DynamicImage {
+	match (color.has_color(), color.has_alpha()) {
+		(true, true) => ImageRgba8(image.to_rgba8()),
+		(true, false) => ImageRgb8(image.to_rgb8()),
+		(false, true) => ImageLumaA8(image.to_luma_alpha8()),
+		(false, false) => ImageLuma8(image.to_luma8()),
+	}
+}
+
+/// Converts the image to the channel layout of `color` at the given bit depth,
+/// `Original` keeps the depth of `color` (float layouts become 16-bit).
+pub fn convert_layout(source: &DynamicImage, color: ColorType, depth: BitDepth) -> DynamicImage {
+	let sixteen = match depth {
+		BitDepth::Original => color.bytes_per_pixel() > color.channel_count(),
+		BitDepth::Eight => false,
+		BitDepth::Sixteen => true,
+	};
+
+	match (sixteen, color.has_color(), color.has_alpha()) {
+		(false, true, true) => ImageRgba8(source.to_rgba8()),
+		(false, true, false) => ImageRgb8(source.to_rgb8()),
+		(false, false, true) => ImageLumaA8(source.to_luma_alpha8()),
+		(false, false, false) => ImageLuma8(source.to_luma8()),
+		(true, true, true) => ImageRgba16(source.to_rgba16()),
+		(true, true, false) => ImageRgb16(source.to_rgb16()),
+		(true, false, true) => ImageLumaA16(source.to_luma_alpha16()),
+		(true, false, false) => ImageLuma16(source.to_luma16()),
+	}
+}
+
+/// Drops the alpha channel, keeping color and bit depth.
+pub fn remove_alpha(source: &DynamicImage) -> Cow<'_, DynamicImage> {
+	match source {
+		ImageLumaA8(_) => Cow::Owned(ImageLuma8(source.to_luma8())),
+		ImageLumaA16(_) => Cow::Owned(ImageLuma16(source.to_luma16())),
+		ImageRgba8(_) => Cow::Owned(ImageRgb8(source.to_rgb8())),
+		ImageRgba16(_) => Cow::Owned(ImageRgb16(source.to_rgb16())),
+		ImageRgba32F(_) => Cow::Owned(ImageRgb32F(source.to_rgb32f())),
+		_ => Cow::Borrowed(source),
+	}
+}
+
+/// Converts `image` to the layout of `color` with the alpha channel of `alpha`.
+pub fn restore_alpha(image: &DynamicImage, alpha: &DynamicImage, color: ColorType) -> DynamicImage {
+	if !color.has_alpha() {
+		return convert_layout(image, color, BitDepth::Original);
+	}
+
+	let mut rgba = image.to_rgba32f();
+
+	for (pixel, alpha) in rgba.pixels_mut().zip(alpha.to_rgba32f().pixels()) {
+		pixel[3] = alpha[3];
+	}
+
+	convert_layout(&ImageRgba32F(rgba), color, BitDepth::Original)
+}
+
+/// Composites the image onto an opaque background and drops the alpha channel.
+fn flatten(source: &DynamicImage, background: [u8; 3]) -> DynamicImage {
+	let background = background.map(|channel| channel as f32 / 255.0);
+	let mut rgba = source.to_rgba32f();
+
+	for pixel in rgba.pixels_mut() {
+		let alpha = pixel[3];
+
+		for (channel, background) in pixel.0[..3].iter_mut().zip(background) {
+			*channel = *channel * alpha + background * (1.0 - alpha);
+		}
+		pixel[3] = 1.0;
+	}
+
+	remove_alpha(&convert_layout(&ImageRgba32F(rgba), source.color(), BitDepth::Original)).into_owned()
+}
+
+fn png_compression(compression: PngCompression) -> CompressionType {
+	match compression {
+		PngCompression::Default => CompressionType::Default,
+		PngCompression::Fast => CompressionType::Fast,
+		PngCompression::Best => CompressionType::Best,
+	}
+}
+
+fn png_filter(filter: PngFilter) -> FilterType {
+	match filter {
+		PngFilter::NoFilter => FilterType::NoFilter,
+		PngFilter::Sub => FilterType::Sub,
+		PngFilter::Up => FilterType::Up,
+		PngFilter::Avg => FilterType::Avg,
+		PngFilter::Paeth => FilterType::Paeth,
+		PngFilter::Adaptive => FilterType::Adaptive,
+	}
+}
+
+/// Converts the image to the requested bit depth, keeping its channel layout.
+fn convert_depth(source: &DynamicImage, depth: BitDepth) -> Cow<'_, DynamicImage> {
+	match depth {
+		BitDepth::Original => Cow::Borrowed(source),
+		_ => Cow::Owned(convert_layout(source, source.color(), depth)),
 	}
 }
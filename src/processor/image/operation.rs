@@ -1,98 +1,219 @@
 use crate::config::operation::*;
+use crate::config::enumerations::BitDepth;
+use crate::config::enumerations::Border;
+use crate::config::enumerations::CropPosition;
+use crate::config::enumerations::Dither;
+use crate::config::enumerations::FlipDirection;
+use super::BAYER_8X8;
+use super::convert_layout;
 use super::Image;
 use super::Modifier;
 
 use anyhow::Result;
 use anyhow::anyhow;
 
+use image::DynamicImage;
 use image::DynamicImage::*;
 use image::GrayImage;
+use image::ImageBuffer;
+use image::Pixel;
+use image::Primitive;
 use image::RgbImage;
 use image::RgbaImage;
+use image::Rgba32FImage;
+
+use imageproc::definitions::Clamp;
+use imageproc::geometric_transformations::warp_into_with;
+use imageproc::geometric_transformations::Interpolation;
+use imageproc::geometric_transformations::Projection;
 
 use rgb::FromSlice;
-use num_traits::AsPrimitive;
+use resize::Type;
+use conv::ValueInto;
+use rand::prelude::*;
 
 impl Modifier for Resize {
 	fn apply(&self, image: &mut Image) -> Result<()> {
-		let w = image.width() as usize;
-		let h = image.height() as usize;
+		let (width, height) = self.size(image.width(), image.height())?;
 
-		let nw = self.width()? as usize;
-		let nh = self.height()? as usize;
+		*image.source_mut() = resize_image(image.source(), width, height, self.filter())?;
 
-		let color = image.color();
-		let channel_count = color.channel_count() as usize;
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
 
-		let filter = self.filter();
-		let mut dst = vec![0u8; (nw * nh * channel_count).as_()];
+impl Modifier for MarkSize {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		image.mark_size(self.id());
 
-		let gray = image
-			.source()
-			.as_luma8()
-			.and_then(|luma| Some(luma.as_gray()));
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
 
-		let rgb = image
-			.source()
-			.as_rgb8()
-			.and_then(|rgb| Some(rgb.as_rgb()));
+impl Modifier for RestoreSize {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let (width, height) = image
+			.marked_size(self.mark())
+			.ok_or(anyhow!("size mark {} was not applied before", self.mark()))?;
 
-		let rgba = image
-			.source()
-			.as_rgba8()
-			.and_then(|rgba| Some(rgba.as_rgba()));
+		let filter = self.filter()?;
 
-		if let Some(gray) = gray {
-			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::Gray8, filter)?;
-			resizer.resize(gray, dst.as_gray_mut())?;
+		if (width, height) != (image.width(), image.height()) {
+			*image.source_mut() = resize_image(image.source(), width, height, filter.resize_type())?;
+		}
+
+		image.record(self.id(), "filter", filter)?;
 
-			let mut buffer = GrayImage::new(nw.as_(), nh.as_());
-			buffer
-				.iter_mut()
-				.zip(dst.iter())
-				.for_each(|(buf, dst)| {
-					*buf = *dst;
-				});
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for ProgressiveDownsample {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let scale = self.scale()?;
+		let steps = self.steps()?;
+		let shift = self.shift()?;
+
+		let mut rng = thread_rng();
+		let (width, height) = (image.width(), image.height());
+
+		// Shifting before downsampling moves the sampling grid instead of blurring the result
+		let (dx, dy) = (rng.gen_range(-shift..=shift), rng.gen_range(-shift..=shift));
 
-			*image.source_mut() = ImageLuma8(buffer);
+		if dx != 0.0 || dy != 0.0 {
+			let (hx, hy) = (dx / scale, dy / scale);
+			let mapping = |x: f32, y: f32| (x - hx, y - hy);
 
-			return Ok(());
+			*image.source_mut() = remap(image.source(), mapping, Interpolation::Bicubic, Border::Edge, 0.0)?;
 		}
 
-		if let Some(rgb) = rgb {
-			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::RGB8, filter)?;
-			resizer.resize(rgb, dst.as_rgb_mut())?;
+		// The overall scale is split into random factors in log space
+		let weights: Vec<f32> = (0..steps).map(|_| rng.gen_range(0.1..1.0)).collect();
+		let total: f32 = weights.iter().sum();
+
+		let mut progress = 1.0;
+		let mut scales = Vec::new();
+		let mut filters = Vec::new();
+
+		for (step, weight) in weights.iter().enumerate() {
+			progress *= scale.powf(weight / total);
 
-			let mut buffer = RgbImage::new(nw.as_(), nh.as_());
-			buffer
-				.iter_mut()
-				.zip(dst.iter())
-				.for_each(|(buf, dst)| {
-					*buf = *dst;
-				});
+			let (target_width, target_height) = if step + 1 == weights.len() {
+				(width as f32 * scale, height as f32 * scale)
+			} else {
+				(width as f32 * progress, height as f32 * progress)
+			};
 
-			*image.source_mut() = ImageRgb8(buffer);
+			let target_width = (target_width.round() as u32).max(1);
+			let target_height = (target_height.round() as u32).max(1);
 
-			return Ok(());
+			let filter = self.filter()?;
+			*image.source_mut() = resize_image(image.source(), target_width, target_height, filter.resize_type())?;
+
+			scales.push(progress);
+			filters.push(filter);
 		}
 
-		if let Some(rgba) = rgba {
-			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::RGBA8, filter)?;
-			resizer.resize(rgba, dst.as_rgba_mut())?;
+		image.record(self.id(), "scales", scales)?;
+		image.record(self.id(), "filters", filters)?;
+		image.record(self.id(), "shift", [dx, dy])?;
 
-			let mut buffer = RgbaImage::new(nw.as_(), nh.as_());
-			buffer
-				.iter_mut()
-				.zip(dst.iter())
-				.for_each(|(buf, dst)| {
-					*buf = *dst;
-				});
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Pixelate {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let size = self.size()?;
+		let color = image.color();
+
+		// Shifts the block grid, the first row and column of blocks get cut short
+		let (ox, oy) = if self.random_offset()? {
+			let mut rng = thread_rng();
+			(rng.gen_range(0..size), rng.gen_range(0..size))
+		} else {
+			(0, 0)
+		};
+
+		let mut buffer = image.source().to_rgba32f();
+		let (width, height) = buffer.dimensions();
+
+		for (y0, y1) in blocks(oy, size, height) {
+			for (x0, x1) in blocks(ox, size, width) {
+				let mut sum = [0f32; 4];
+
+				for y in y0..y1 {
+					for x in x0..x1 {
+						for (sum, channel) in sum.iter_mut().zip(buffer.get_pixel(x, y).0) {
+							*sum += channel;
+						}
+					}
+				}
+
+				let count = ((x1 - x0) * (y1 - y0)) as f32;
+				let average = sum.map(|sum| sum / count);
 
-			*image.source_mut() = ImageRgba8(buffer);
-			return Ok(());
+				for y in y0..y1 {
+					for x in x0..x1 {
+						buffer.get_pixel_mut(x, y).0 = average;
+					}
+				}
+			}
 		}
 
-		Err(anyhow!("unsupported color"))
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "offset", [ox, oy])?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Decimate {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let factor = self.factor()?;
+
+		let (ox, oy) = if self.random_offset()? {
+			let mut rng = thread_rng();
+			(rng.gen_range(0..factor.min(image.width())), rng.gen_range(0..factor.min(image.height())))
+		} else {
+			(0, 0)
+		};
+
+		let offset = (ox, oy);
+
+		*image.source_mut() = match image.source() {
+			ImageLuma8(image) => ImageLuma8(decimate(image, factor, offset)),
+			ImageLuma16(image) => ImageLuma16(decimate(image, factor, offset)),
+			ImageLumaA8(image) => ImageLumaA8(decimate(image, factor, offset)),
+			ImageLumaA16(image) => ImageLumaA16(decimate(image, factor, offset)),
+			ImageRgb8(image) => ImageRgb8(decimate(image, factor, offset)),
+			ImageRgb16(image) => ImageRgb16(decimate(image, factor, offset)),
+			ImageRgb32F(image) => ImageRgb32F(decimate(image, factor, offset)),
+			ImageRgba8(image) => ImageRgba8(decimate(image, factor, offset)),
+			ImageRgba16(image) => ImageRgba16(decimate(image, factor, offset)),
+			ImageRgba32F(image) => ImageRgba32F(decimate(image, factor, offset)),
+			_ => return Err(anyhow!("unsupported color")),
+		};
+
+		image.record(self.id(), "offset", [ox, oy])?;
+
+		Ok(())
 	}
 	fn id(&self) -> &str {
 		self.id.as_ref()
@@ -146,3 +267,620 @@ impl Modifier for ToRgb {
 		&self.id
 	}
 }
+
+impl Modifier for Quantize {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let bits = self.bits()?;
+		let dither = self.dither()?;
+		let color = image.color();
+
+		let mut buffer = image.source().to_rgba32f();
+		quantize_levels(&mut buffer, bits, dither);
+
+		// Everything up to 8 bits per channel fits the 8-bit layout exactly
+		let depth = if bits.iter().all(|bits| *bits <= 8) { BitDepth::Eight } else { BitDepth::Sixteen };
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, depth);
+		image.record(self.id(), "bits", bits)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Flip {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		*image.source_mut() = match self.direction()? {
+			FlipDirection::Horizontal => image.source().fliph(),
+			FlipDirection::Vertical => image.source().flipv(),
+			FlipDirection::Both => image.source().rotate180(),
+		};
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Rotate90 {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		*image.source_mut() = match self.turns()? {
+			1 => image.source().rotate90(),
+			2 => image.source().rotate180(),
+			3 => image.source().rotate270(),
+			_ => return Ok(()),
+		};
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Rotate {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let (sin, cos) = self.angle()?.to_radians().sin_cos();
+		let interpolation = self.interpolation()?.interpolation_type();
+		let border = self.border()?;
+		let value = self.value()?;
+
+		let cx = (image.width() as f32 - 1.0) / 2.0;
+		let cy = (image.height() as f32 - 1.0) / 2.0;
+
+		// Pre-image of every output pixel, y points down so this turns counterclockwise
+		let mapping = |x: f32, y: f32| {
+			let (dx, dy) = (x - cx, y - cy);
+			(cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+		};
+
+		*image.source_mut() = remap(image.source(), mapping, interpolation, border, value)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Crop {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let width = self.width()?;
+		let height = self.height()?;
+
+		if width == 0 || height == 0 || width > image.width() || height > image.height() {
+			return Err(anyhow!("crop size does not fit the image"));
+		}
+
+		let (x_range, y_range) = (image.width() - width, image.height() - height);
+
+		let (x, y) = match self.origin()? {
+			Some((x, y)) if x <= x_range && y <= y_range => (x, y),
+			Some(_) => return Err(anyhow!("crop origin is out of bounds")),
+			None => match self.position()? {
+				CropPosition::Center => (x_range / 2, y_range / 2),
+				CropPosition::Random => {
+					let mut rng = thread_rng();
+					(rng.gen_range(0..=x_range), rng.gen_range(0..=y_range))
+				}
+			},
+		};
+
+		*image.source_mut() = image.source().crop_imm(x, y, width, height);
+
+		image.record(self.id(), "x", x)?;
+		image.record(self.id(), "y", y)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Pad {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let margins = self.margins()?;
+		let border = self.border()?;
+		let value = self.value()?;
+
+		*image.source_mut() = pad_image(image.source(), margins, border, value)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Perspective {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let strength = self.strength()?;
+		let interpolation = self.interpolation()?.interpolation_type();
+		let border = self.border()?;
+		let value = self.value()?;
+
+		let (width, height) = (image.width() as f32, image.height() as f32);
+		let corners = [(0.0, 0.0), (width - 1.0, 0.0), (width - 1.0, height - 1.0), (0.0, height - 1.0)];
+
+		let mut rng = thread_rng();
+		let moved = corners.map(|(x, y)| {
+			let dx = rng.gen_range(-1.0f32..=1.0) * strength * width;
+			let dy = rng.gen_range(-1.0f32..=1.0) * strength * height;
+			(x + dx, y + dy)
+		});
+
+		let matrix = homography(corners, moved).ok_or(anyhow!("degenerate perspective"))?;
+		warp(image, matrix, interpolation, border, value)?;
+
+		image.record(self.id(), "matrix", matrix)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Affine {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let (sin, cos) = self.rotation()?.to_radians().sin_cos();
+		let scale = self.scale()?;
+		let shear = self.shear()?.to_radians().tan();
+		let (tx, ty) = self.translation()?;
+
+		let interpolation = self.interpolation()?.interpolation_type();
+		let border = self.border()?;
+		let value = self.value()?;
+
+		let cx = (image.width() as f32 - 1.0) / 2.0;
+		let cy = (image.height() as f32 - 1.0) / 2.0;
+
+		// Scale, shear and rotate about the center, then translate
+		let linear = matmul(
+			[[cos, sin, 0.0], [-sin, cos, 0.0], [0.0, 0.0, 1.0]],
+			[[scale, shear * scale, 0.0], [0.0, scale, 0.0], [0.0, 0.0, 1.0]],
+		);
+		let matrix = matmul(
+			[[1.0, 0.0, cx + tx * image.width() as f32], [0.0, 1.0, cy + ty * image.height() as f32], [0.0, 0.0, 1.0]],
+			matmul(linear, [[1.0, 0.0, -cx], [0.0, 1.0, -cy], [0.0, 0.0, 1.0]]),
+		);
+
+		warp(image, matrix, interpolation, border, value)?;
+
+		image.record(self.id(), "matrix", matrix)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for LensDistortion {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let (k1, k2) = self.coefficients()?;
+		let interpolation = self.interpolation()?.interpolation_type();
+		let border = self.border()?;
+		let value = self.value()?;
+
+		let cx = (image.width() as f32 - 1.0) / 2.0;
+		let cy = (image.height() as f32 - 1.0) / 2.0;
+		let radius = (cx * cx + cy * cy).sqrt().max(1.0);
+
+		// Radius is normalized to the half diagonal, so the corners sit at 1
+		let mapping = |x: f32, y: f32| {
+			let (dx, dy) = (x - cx, y - cy);
+			let r2 = (dx * dx + dy * dy) / (radius * radius);
+			let factor = 1.0 + k1 * r2 + k2 * r2 * r2;
+
+			(cx + dx * factor, cy + dy * factor)
+		};
+
+		*image.source_mut() = remap(image.source(), mapping, interpolation, border, value)?;
+
+		image.record(self.id(), "k1", k1)?;
+		image.record(self.id(), "k2", k2)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Applies a row-major homogeneous transform mapping input to output positions.
+fn warp(image: &mut Image, matrix: [[f32; 3]; 3], interpolation: Interpolation, border: Border, value: f32) -> Result<()> {
+	let [a, b, c] = matrix;
+
+	let projection = Projection::from_matrix([a[0], a[1], a[2], b[0], b[1], b[2], c[0], c[1], c[2]])
+		.ok_or(anyhow!("transform is not invertible"))?
+		.invert();
+
+	*image.source_mut() = remap(image.source(), |x, y| projection * (x, y), interpolation, border, value)?;
+
+	Ok(())
+}
+
+fn matmul(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+	let mut product = [[0.0; 3]; 3];
+
+	for (row, a) in product.iter_mut().zip(a) {
+		for (column, value) in row.iter_mut().enumerate() {
+			*value = (0..3).map(|k| a[k] * b[k][column]).sum();
+		}
+	}
+
+	product
+}
+
+/// Projective transform taking every `from` point onto its `to` point.
+fn homography(from: [(f32, f32); 4], to: [(f32, f32); 4]) -> Option<[[f32; 3]; 3]> {
+	let mut system = [[0f64; 9]; 8];
+
+	for (i, ((x, y), (u, v))) in from.into_iter().zip(to).enumerate() {
+		let (x, y, u, v) = (x as f64, y as f64, u as f64, v as f64);
+
+		system[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+		system[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+	}
+
+	// Gaussian elimination with partial pivoting, the last column holds the right hand side
+	for column in 0..8 {
+		let pivot = (column..8).max_by(|a, b| system[*a][column].abs().total_cmp(&system[*b][column].abs()))?;
+
+		if system[pivot][column].abs() < 1e-12 {
+			return None;
+		}
+
+		system.swap(column, pivot);
+
+		let pivot = system[column];
+
+		for (index, row) in system.iter_mut().enumerate() {
+			if index != column {
+				let factor = row[column] / pivot[column];
+
+				for (value, pivot) in row.iter_mut().zip(pivot).skip(column) {
+					*value -= factor * pivot;
+				}
+			}
+		}
+	}
+
+	let h: Vec<f32> = (0..8).map(|i| (system[i][8] / system[i][i]) as f32).collect();
+
+	Some([[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], 1.0]])
+}
+
+/// Resamples the image where `mapping` gives the source position of every output pixel.
+/// Positions outside the image are filled according to `border`, a constant border
+/// sets every channel (alpha included) to `value`.
+pub fn remap<F>(source: &DynamicImage, mapping: F, interpolation: Interpolation, border: Border, value: f32) -> Result<DynamicImage>
+where
+	F: Fn(f32, f32) -> (f32, f32) + Sync + Send,
+{
+	Ok(match source {
+		ImageLuma8(image) => ImageLuma8(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageLuma16(image) => ImageLuma16(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageLumaA8(image) => ImageLumaA8(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageLumaA16(image) => ImageLumaA16(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageRgb8(image) => ImageRgb8(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageRgb16(image) => ImageRgb16(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageRgb32F(image) => ImageRgb32F(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageRgba8(image) => ImageRgba8(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageRgba16(image) => ImageRgba16(remap_buffer(image, &mapping, interpolation, border, value)),
+		ImageRgba32F(image) => ImageRgba32F(remap_buffer(image, &mapping, interpolation, border, value)),
+		_ => return Err(anyhow!("unsupported color")),
+	})
+}
+
+fn remap_buffer<P, F>(
+	image: &ImageBuffer<P, Vec<P::Subpixel>>,
+	mapping: &F,
+	interpolation: Interpolation,
+	border: Border,
+	value: f32,
+) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+	P: Pixel + Send + Sync,
+	P::Subpixel: ValueInto<f32> + Clamp<f32> + Send + Sync,
+	F: Fn(f32, f32) -> (f32, f32) + Sync + Send,
+{
+	let fill = fill_pixel::<P>(value);
+	let (width, height) = image.dimensions();
+
+	// The warp only knows constant borders and rejects samples whose kernel touches
+	// the last row or column. Reflect and edge borders fold every position back into
+	// the image, so the margin only has to cover the bicubic kernel.
+	let margin = 3;
+	let padded = pad(image, [margin; 4], border, value);
+	let offset = margin as f32;
+
+	let fold = |position: f32, size: u32| {
+		let last = (size - 1) as f32;
+
+		match border {
+			Border::Constant => position,
+			Border::Edge => position.clamp(0.0, last),
+			Border::Reflect if last == 0.0 => 0.0,
+			Border::Reflect => {
+				let period = 2.0 * last;
+				let position = position.rem_euclid(period);
+
+				if position > last { period - position } else { position }
+			}
+		}
+	};
+
+	let mut output = ImageBuffer::new(width, height);
+
+	warp_into_with(
+		&padded,
+		|x, y| {
+			let (sx, sy) = mapping(x, y);
+			(fold(sx, width) + offset, fold(sy, height) + offset)
+		},
+		interpolation,
+		fill,
+		&mut output,
+	);
+
+	output
+}
+
+/// Pads the image on all sides, see [`pad`].
+pub fn pad_image(source: &DynamicImage, margins: [u32; 4], border: Border, value: f32) -> Result<DynamicImage> {
+	Ok(match source {
+		ImageLuma8(image) => ImageLuma8(pad(image, margins, border, value)),
+		ImageLuma16(image) => ImageLuma16(pad(image, margins, border, value)),
+		ImageLumaA8(image) => ImageLumaA8(pad(image, margins, border, value)),
+		ImageLumaA16(image) => ImageLumaA16(pad(image, margins, border, value)),
+		ImageRgb8(image) => ImageRgb8(pad(image, margins, border, value)),
+		ImageRgb16(image) => ImageRgb16(pad(image, margins, border, value)),
+		ImageRgb32F(image) => ImageRgb32F(pad(image, margins, border, value)),
+		ImageRgba8(image) => ImageRgba8(pad(image, margins, border, value)),
+		ImageRgba16(image) => ImageRgba16(pad(image, margins, border, value)),
+		ImageRgba32F(image) => ImageRgba32F(pad(image, margins, border, value)),
+		_ => return Err(anyhow!("unsupported color")),
+	})
+}
+
+/// Adds top, bottom, left and right margins filled according to `border`.
+pub fn pad<P>(image: &ImageBuffer<P, Vec<P::Subpixel>>, margins: [u32; 4], border: Border, value: f32) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+	P: Pixel,
+	P::Subpixel: ValueInto<f32> + Clamp<f32>,
+{
+	let [top, bottom, left, right] = margins;
+	let (width, height) = image.dimensions();
+	let fill = fill_pixel::<P>(value);
+
+	ImageBuffer::from_fn(width + left + right, height + top + bottom, |x, y| {
+		let (x, y) = (x as i64 - left as i64, y as i64 - top as i64);
+
+		if x >= 0 && y >= 0 && x < width as i64 && y < height as i64 {
+			return *image.get_pixel(x as u32, y as u32);
+		}
+
+		match border {
+			Border::Constant => fill,
+			Border::Reflect => *image.get_pixel(reflect(x, width), reflect(y, height)),
+			Border::Edge => *image.get_pixel(x.clamp(0, width as i64 - 1) as u32, y.clamp(0, height as i64 - 1) as u32),
+		}
+	})
+}
+
+/// Ranges covered by blocks of `size` with grid lines at `offset + k * size`.
+fn blocks(offset: u32, size: u32, length: u32) -> Vec<(u32, u32)> {
+	let mut bounds = vec![0];
+
+	bounds.extend((offset..length).step_by(size as usize).filter(|bound| *bound > 0));
+	bounds.push(length);
+
+	bounds.windows(2).map(|bounds| (bounds[0], bounds[1])).collect()
+}
+
+/// Keeps every `factor`-th pixel starting at `offset`, without any low-pass filter.
+fn decimate<P>(image: &ImageBuffer<P, Vec<P::Subpixel>>, factor: u32, (ox, oy): (u32, u32)) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+	P: Pixel,
+{
+	let width = (image.width() - ox).div_ceil(factor);
+	let height = (image.height() - oy).div_ceil(factor);
+
+	ImageBuffer::from_fn(width, height, |x, y| *image.get_pixel(ox + x * factor, oy + y * factor))
+}
+
+/// Mirrors an index into `0..size` without repeating the edge sample.
+pub fn reflect(mut i: i64, size: u32) -> u32 {
+	let last = size as i64 - 1;
+
+	if last <= 0 {
+		return 0;
+	}
+
+	while i < 0 || i > last {
+		i = if i < 0 { -i } else { 2 * last - i };
+	}
+
+	i as u32
+}
+
+fn fill_pixel<P>(value: f32) -> P where P: Pixel, P::Subpixel: ValueInto<f32> + Clamp<f32> {
+	let max: f32 = P::Subpixel::DEFAULT_MAX_VALUE.value_into().unwrap_or(1.0);
+	let channel: P::Subpixel = Clamp::clamp(value.clamp(0.0, 1.0) * max);
+
+	*P::from_slice(&[channel; 4][..P::CHANNEL_COUNT as usize])
+}
+
+/// Resizes 8-bit luma, RGB and RGBA directly, every other layout through float RGBA.
+pub fn resize_image(source: &DynamicImage, width: u32, height: u32, filter: Type) -> Result<DynamicImage> {
+	let (w, h) = (source.width() as usize, source.height() as usize);
+	let (nw, nh) = (width as usize, height as usize);
+
+	match source {
+		ImageLuma8(luma) => {
+			let mut dst = vec![0u8; nw * nh];
+
+			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::Gray8, filter)?;
+			resizer.resize(luma.as_gray(), dst.as_gray_mut())?;
+
+			Ok(ImageLuma8(GrayImage::from_raw(width, height, dst).ok_or(anyhow!("resize"))?))
+		}
+		ImageRgb8(rgb) => {
+			let mut dst = vec![0u8; nw * nh * 3];
+
+			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::RGB8, filter)?;
+			resizer.resize(rgb.as_rgb(), dst.as_rgb_mut())?;
+
+			Ok(ImageRgb8(RgbImage::from_raw(width, height, dst).ok_or(anyhow!("resize"))?))
+		}
+		ImageRgba8(rgba) => {
+			let mut dst = vec![0u8; nw * nh * 4];
+
+			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::RGBA8, filter)?;
+			resizer.resize(rgba.as_rgba(), dst.as_rgba_mut())?;
+
+			Ok(ImageRgba8(RgbaImage::from_raw(width, height, dst).ok_or(anyhow!("resize"))?))
+		}
+		_ => {
+			let rgba = source.to_rgba32f();
+			let mut dst = vec![0f32; nw * nh * 4];
+
+			let mut resizer = resize::new(w, h, nw, nh, resize::Pixel::RGBAF32, filter)?;
+			resizer.resize(rgba.as_rgba(), dst.as_rgba_mut())?;
+
+			let buffer = Rgba32FImage::from_raw(width, height, dst).ok_or(anyhow!("resize"))?;
+
+			Ok(convert_layout(&ImageRgba32F(buffer), source.color(), BitDepth::Original))
+		}
+	}
+}
+
+/// Rounds the color channels to `2^bits` levels each, alpha is left untouched.
+fn quantize_levels(buffer: &mut Rgba32FImage, bits: [u32; 3], dither: Dither) {
+	let (width, height) = buffer.dimensions();
+
+	let levels = bits.map(|bits| ((1u32 << bits) - 1) as f32);
+	let mut error = vec![[0f32; 3]; (width * height) as usize];
+
+	for y in 0..height {
+		for x in 0..width {
+			let pixel = buffer.get_pixel_mut(x, y);
+
+			let offset = match dither {
+				Dither::None => [0.0; 3],
+				Dither::Bayer => {
+					let threshold = (BAYER_8X8[(y % 8) as usize][(x % 8) as usize] as f32 + 0.5) / 64.0 - 0.5;
+					[threshold; 3]
+				}
+				Dither::FloydSteinberg => error[(y * width + x) as usize],
+			};
+
+			for channel in 0..3 {
+				let target = pixel[channel] * levels[channel] + offset[channel];
+				let level = target.round().clamp(0.0, levels[channel]);
+
+				if dither == Dither::FloydSteinberg {
+					let mut diffuse = |dx: i64, dy: i64, weight: f32| {
+						let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+
+						if nx >= 0 && nx < width as i64 && ny < height as i64 {
+							error[(ny * width as i64 + nx) as usize][channel] += (target - level) * weight;
+						}
+					};
+
+					diffuse(1, 0, 7.0 / 16.0);
+					diffuse(-1, 1, 3.0 / 16.0);
+					diffuse(0, 1, 5.0 / 16.0);
+					diffuse(1, 1, 1.0 / 16.0);
+				}
+
+				pixel[channel] = level / levels[channel];
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use image::GrayImage;
+	use image::Luma;
+	use image::Rgba;
+
+	fn project(matrix: &[[f32; 3]; 3], (x, y): (f32, f32)) -> (f32, f32) {
+		let [u, v, w] = matrix.map(|row| row[0] * x + row[1] * y + row[2]);
+		(u / w, v / w)
+	}
+
+	#[test]
+	fn sixteen_bit_quantize_keeps_every_level() {
+		let buffer = Rgba32FImage::from_fn(16, 8, |x, y| {
+			let value = (y * 16 + x) * 511 + 17;
+			Rgba([value, u16::MAX as u32 - value, x * 4099, u16::MAX as u32].map(|value| value as f32 / u16::MAX as f32))
+		});
+
+		for dither in [Dither::None, Dither::FloydSteinberg] {
+			let mut quantized = buffer.clone();
+			quantize_levels(&mut quantized, [16; 3], dither);
+
+			assert_eq!(quantized, buffer, "{:?}", dither);
+		}
+	}
+
+	#[test]
+	fn blocks_cover_lengths_that_are_not_a_multiple() {
+		assert_eq!(blocks(0, 4, 10), [(0, 4), (4, 8), (8, 10)]);
+		assert_eq!(blocks(3, 4, 10), [(0, 3), (3, 7), (7, 10)]);
+		assert_eq!(blocks(0, 16, 10), [(0, 10)]);
+
+		for size in 1..=12 {
+			for offset in 0..size {
+				let blocks = blocks(offset, size, 10);
+
+				assert_eq!((blocks[0].0, blocks[blocks.len() - 1].1), (0, 10));
+				assert!(blocks.windows(2).all(|pair| pair[0].1 == pair[1].0));
+				assert!(blocks.iter().all(|(start, end)| start < end && end - start <= size), "{} {} {:?}", offset, size, blocks);
+			}
+		}
+	}
+
+	#[test]
+	fn decimate_keeps_partial_last_block() {
+		let image = GrayImage::from_fn(10, 7, |x, y| Luma([(y * 10 + x) as u8]));
+
+		let decimated = decimate(&image, 3, (0, 0));
+		assert_eq!(decimated.dimensions(), (4, 3));
+		assert_eq!(decimated.get_pixel(3, 2)[0], 69);
+
+		let decimated = decimate(&image, 3, (2, 1));
+		assert_eq!(decimated.dimensions(), (3, 2));
+		assert_eq!(decimated.get_pixel(2, 1)[0], 48);
+	}
+
+	#[test]
+	fn homography_maps_corners_onto_targets() {
+		let from = [(0.0, 0.0), (199.0, 0.0), (199.0, 149.0), (0.0, 149.0)];
+		let to = [(12.5, -4.0), (190.0, 8.0), (205.0, 140.0), (-6.0, 155.5)];
+
+		let matrix = homography(from, to).expect("homography");
+
+		for (source, target) in from.into_iter().zip(to) {
+			let (u, v) = project(&matrix, source);
+			assert!((u - target.0).abs() < 1e-2 && (v - target.1).abs() < 1e-2, "{:?} went to {:?}", source, (u, v));
+		}
+	}
+
+	#[test]
+	fn homography_rejects_collinear_points() {
+		let from = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)];
+
+		assert!(homography(from, from).is_none());
+	}
+}
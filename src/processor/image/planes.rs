@@ -0,0 +1,210 @@
+use crate::config::enumerations::BitDepth;
+use crate::config::enumerations::ColorSpace;
+use super::convert_layout;
+use super::Image;
+use super::Modifier;
+
+use image::DynamicImage;
+use image::DynamicImage::*;
+use image::GrayImage;
+use image::ImageBuffer;
+use image::Luma;
+use image::Rgba32FImage;
+use image::Rgba;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+type Gray16Image = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+impl Image {
+	/// Splits the image into the planes of `space`, followed by alpha if the image has one.
+	/// Planes are 8-bit for 8-bit images and 16-bit otherwise,
+	/// grayscale images are treated as RGB with equal channels.
+	pub fn separate_planes(&self, space: ColorSpace) -> Result<Vec<DynamicImage>> {
+		let rgba = self.source().to_rgba32f();
+		let (width, height) = rgba.dimensions();
+		let max = plane_max(self.source());
+
+		let mut planes = vec![Gray16Image::new(width, height); 4];
+
+		for (x, y, pixel) in rgba.enumerate_pixels() {
+			let [r, g, b, a] = pixel.0.map(|value| value * max);
+			let [c0, c1, c2] = to_space(space, [r, g, b], max);
+
+			planes[0].put_pixel(x, y, Luma([quantize(c0, max)]));
+			planes[1].put_pixel(x, y, Luma([quantize(c1, max)]));
+			planes[2].put_pixel(x, y, Luma([quantize(c2, max)]));
+			planes[3].put_pixel(x, y, Luma([quantize(a, max)]));
+		}
+
+		if !self.color().has_alpha() {
+			planes.truncate(3);
+		}
+
+		planes.into_iter().map(|plane| to_plane(plane, max)).collect()
+	}
+	/// Inverse of [`Image::separate_planes`], keeps the channel layout and depth of the image.
+	pub fn combine_planes(&self, space: ColorSpace, planes: Vec<DynamicImage>) -> Result<DynamicImage> {
+		let expected = if self.color().has_alpha() { 4 } else { 3 };
+
+		if planes.len() != expected {
+			return Err(anyhow!("expected {} planes, got {}", expected, planes.len()));
+		}
+
+		let max = plane_max(&planes[0]);
+		let (width, height) = (planes[0].width(), planes[0].height());
+
+		let values = planes
+			.iter()
+			.map(|plane| plane_values(plane, width, height))
+			.collect::<Result<Vec<_>>>()?;
+
+		let mut rgba = Rgba32FImage::new(width, height);
+
+		for (index, pixel) in rgba.pixels_mut().enumerate() {
+			let channels = [values[0][index], values[1][index], values[2][index]];
+			let alpha = values.get(3).map_or(max, |alpha| alpha[index]);
+
+			let [r, g, b] = from_space(space, channels, max);
+			*pixel = Rgba([r / max, g / max, b / max, alpha / max]);
+		}
+
+		Ok(convert_layout(&ImageRgba32F(rgba), self.color(), BitDepth::Original))
+	}
+	/// Runs the modifier on a single plane as if it were a grayscale image.
+	/// Records of the modifier are kept, the plane must keep its size and depth.
+	pub fn apply_to_plane(&mut self, modifier: &dyn Modifier, plane: DynamicImage) -> Result<DynamicImage> {
+		let (width, height, color) = (plane.width(), plane.height(), plane.color());
+		let mut image = self.derive(plane);
+
+		modifier.apply(&mut image)?;
+		self.records.append(&mut image.records);
+
+		if image.width() != width || image.height() != height {
+			return Err(anyhow!("modifier {} changed the plane size", modifier.id()));
+		}
+
+		Ok(convert_layout(image.source(), color, BitDepth::Original))
+	}
+}
+
+/// Largest plane value, 8-bit images keep 8-bit planes and everything else gets 16 bits.
+fn plane_max(source: &DynamicImage) -> f32 {
+	let color = source.color();
+
+	if color.bytes_per_pixel() == color.channel_count() {
+		u8::MAX as f32
+	} else {
+		u16::MAX as f32
+	}
+}
+
+fn quantize(value: f32, max: f32) -> u16 {
+	value.round().clamp(0.0, max) as u16
+}
+
+fn to_plane(plane: Gray16Image, max: f32) -> Result<DynamicImage> {
+	if max == u8::MAX as f32 {
+		let (width, height) = plane.dimensions();
+		let raw = plane.into_raw().into_iter().map(|value| value as u8).collect();
+
+		Ok(ImageLuma8(GrayImage::from_raw(width, height, raw).ok_or(anyhow!("plane size"))?))
+	} else {
+		Ok(ImageLuma16(plane))
+	}
+}
+
+fn plane_values(plane: &DynamicImage, width: u32, height: u32) -> Result<Vec<f32>> {
+	if plane.width() != width || plane.height() != height {
+		return Err(anyhow!("planes differ in size"));
+	}
+
+	match plane {
+		ImageLuma8(plane) => Ok(plane.iter().map(|value| *value as f32).collect()),
+		ImageLuma16(plane) => Ok(plane.iter().map(|value| *value as f32).collect()),
+		_ => Err(anyhow!("unsupported plane")),
+	}
+}
+
+fn ycbcr_weights(space: ColorSpace) -> (f32, f32) {
+	match space {
+		ColorSpace::YCbCr709 => (0.2126, 0.0722),
+		_ => (0.299, 0.114),
+	}
+}
+
+/// Converts RGB in `0..=max` to `space`, chroma is centered on `(max + 1) / 2`
+/// and hue is stored in `max + 1` steps per turn so it wraps around.
+fn to_space(space: ColorSpace, [r, g, b]: [f32; 3], max: f32) -> [f32; 3] {
+	let half = (max + 1.0) / 2.0;
+
+	match space {
+		ColorSpace::Rgb => [r, g, b],
+		ColorSpace::YCbCr601 | ColorSpace::YCbCr709 => {
+			let (kr, kb) = ycbcr_weights(space);
+
+			let y = kr * r + (1.0 - kr - kb) * g + kb * b;
+			let cb = half + (b - y) / (2.0 * (1.0 - kb));
+			let cr = half + (r - y) / (2.0 * (1.0 - kr));
+
+			[y, cb, cr]
+		}
+		ColorSpace::Hsv => {
+			let value = r.max(g).max(b);
+			let delta = value - r.min(g).min(b);
+
+			let hue = if delta == 0.0 {
+				0.0
+			} else if value == r {
+				((g - b) / delta).rem_euclid(6.0)
+			} else if value == g {
+				(b - r) / delta + 2.0
+			} else {
+				(r - g) / delta + 4.0
+			};
+
+			let saturation = if value == 0.0 { 0.0 } else { delta / value * max };
+			let hue = (hue / 6.0 * (max + 1.0)).round() % (max + 1.0);
+
+			[hue, saturation, value]
+		}
+	}
+}
+
+/// Inverse of [`to_space`].
+fn from_space(space: ColorSpace, [a, b, c]: [f32; 3], max: f32) -> [f32; 3] {
+	let half = (max + 1.0) / 2.0;
+
+	match space {
+		ColorSpace::Rgb => [a, b, c],
+		ColorSpace::YCbCr601 | ColorSpace::YCbCr709 => {
+			let (kr, kb) = ycbcr_weights(space);
+			let (y, cb, cr) = (a, b - half, c - half);
+
+			let r = y + 2.0 * (1.0 - kr) * cr;
+			let b = y + 2.0 * (1.0 - kb) * cb;
+			let g = (y - kr * r - kb * b) / (1.0 - kr - kb);
+
+			[r, g, b]
+		}
+		ColorSpace::Hsv => {
+			let (hue, saturation, value) = (a / (max + 1.0) * 6.0, b / max, c);
+
+			let chroma = value * saturation;
+			let x = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+			let m = value - chroma;
+
+			let (r, g, b) = match hue as u32 {
+				0 => (chroma, x, 0.0),
+				1 => (x, chroma, 0.0),
+				2 => (0.0, chroma, x),
+				3 => (0.0, x, chroma),
+				4 => (x, 0.0, chroma),
+				_ => (chroma, 0.0, x),
+			};
+
+			[r + m, g + m, b + m]
+		}
+	}
+}
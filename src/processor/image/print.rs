@@ -0,0 +1,157 @@
+use crate::config::print::*;
+use crate::config::enumerations::BitDepth;
+use crate::config::enumerations::Border;
+use crate::config::enumerations::HalftoneMode;
+use super::adjustment::standard_normal;
+use super::convert_layout;
+use super::operation::remap;
+use super::Image;
+use super::Modifier;
+
+use anyhow::Result;
+
+use image::DynamicImage::*;
+use image::ImageBuffer;
+use image::Luma;
+
+use imageproc::filter::gaussian_blur_f32;
+use imageproc::geometric_transformations::Interpolation;
+
+use rand::prelude::*;
+
+use std::f32::consts::PI;
+
+// A single screen at 45 degrees is the least visible to the eye
+const GRAY_ANGLE: f32 = 45.0;
+
+impl Modifier for Halftone {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let period = self.dot_size()?;
+		let mode = self.mode()?;
+		let angle = self.angle()?;
+		let angles = self.angles();
+		let color = image.color();
+
+		let mut buffer = image.source().to_rgba32f();
+
+		for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+			let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|value| value.clamp(0.0, 1.0));
+			let dot = |coverage, screen: f32| screen_dot(x as f32, y as f32, period, (screen + angle).to_radians(), coverage);
+
+			let rgb = match mode {
+				HalftoneMode::Gray => {
+					let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+					[1.0 - dot(1.0 - luma, GRAY_ANGLE); 3]
+				}
+				HalftoneMode::Cmyk => {
+					let key = 1.0 - r.max(g).max(b);
+					let ink = |value: f32| if key < 1.0 { (1.0 - value - key) / (1.0 - key) } else { 0.0 };
+
+					let inks = [ink(r), ink(g), ink(b), key];
+					let [c, m, y, k]: [f32; 4] = std::array::from_fn(|i| dot(inks[i], angles[i]));
+
+					[(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)]
+				}
+			};
+
+			pixel.0[..3].copy_from_slice(&rgb);
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "dot_size", period)?;
+		image.record(self.id(), "mode", mode)?;
+		image.record(self.id(), "angle", angle)?;
+		image.record(self.id(), "angles", angles)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for PrintScan {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let grain = self.grain()?;
+		let illumination = self.illumination()?;
+		let skew = self.skew()?;
+		let color = image.color();
+
+		let mut buffer = image.source().to_rgba32f();
+		let (width, height) = buffer.dimensions();
+		let mut rng = thread_rng();
+
+		// Paper fibres, white noise softened to a few pixels
+		let noise = ImageBuffer::from_fn(width, height, |_, _| Luma([standard_normal(&mut rng)]));
+		let texture = gaussian_blur_f32(&noise, 1.0);
+
+		let count = texture.len().max(1) as f32;
+		let deviation = (texture.iter().map(|value| value * value).sum::<f32>() / count).sqrt().max(f32::EPSILON);
+
+		// The lamp is brighter on one side of the page
+		let direction = rng.gen_range(0.0..2.0 * PI);
+		let (sin, cos) = direction.sin_cos();
+
+		let cx = (width as f32 - 1.0) / 2.0;
+		let cy = (height as f32 - 1.0) / 2.0;
+		let extent = (cx * cx + cy * cy).sqrt().max(1.0);
+
+		for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+			let position = ((x as f32 - cx) * cos + (y as f32 - cy) * sin) / extent;
+			let paper = 1.0 + grain * texture.get_pixel(x, y)[0] / deviation;
+			let light = 1.0 - illumination * (position + 1.0) / 2.0;
+
+			for channel in pixel.0[..3].iter_mut() {
+				*channel *= paper * light;
+			}
+		}
+
+		let mut scanned = ImageRgba32F(buffer);
+
+		if skew != 0.0 {
+			let (sin, cos) = skew.to_radians().sin_cos();
+
+			let mapping = |x: f32, y: f32| {
+				let (dx, dy) = (x - cx, y - cy);
+				(cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+			};
+
+			// The scanner lid shows white around the rotated page
+			scanned = remap(&scanned, mapping, Interpolation::Bicubic, Border::Constant, 1.0)?;
+		}
+
+		*image.source_mut() = convert_layout(&scanned, color, BitDepth::Original);
+
+		image.record(self.id(), "grain", grain)?;
+		image.record(self.id(), "illumination", illumination)?;
+		image.record(self.id(), "direction", direction.to_degrees())?;
+		image.record(self.id(), "skew", skew)?;
+
+		// Optics and resolution of the scanner are ordinary modifiers with their own records
+		for modifier in [self.blur(), self.resize()].into_iter().flatten() {
+			modifier.get().apply(image)?;
+		}
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Ink of a clustered round dot screen at `(x, y)`, rotated by `angle` radians.
+/// The dot area follows `coverage` and its edge is antialiased over about a pixel.
+fn screen_dot(x: f32, y: f32, period: f32, angle: f32, coverage: f32) -> f32 {
+	let (sin, cos) = angle.sin_cos();
+	let u = (x * cos + y * sin) / period;
+	let v = (y * cos - x * sin) / period;
+
+	// Peaks in the middle of every cell, thresholding it grows dots into a checkerboard
+	let spot = 0.5 + ((2.0 * PI * u).cos() + (2.0 * PI * v).cos()) / 4.0;
+
+	let edge = PI / (2.0 * period);
+	let threshold = (1.0 - coverage.clamp(0.0, 1.0)) * (1.0 + edge) - edge / 2.0;
+
+	((spot - threshold) / edge + 0.5).clamp(0.0, 1.0)
+}
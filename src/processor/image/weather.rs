@@ -0,0 +1,226 @@
+use crate::config::weather::*;
+use crate::config::enumerations::BitDepth;
+use super::convert_layout;
+use super::operation::resize_image;
+use super::Image;
+use super::Modifier;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+use image::DynamicImage::*;
+use image::ImageBuffer;
+use image::io::Reader;
+use image::Luma;
+
+use imageproc::filter::gaussian_blur_f32;
+
+use resize::Type;
+use rand::prelude::*;
+
+use std::fs;
+use std::path::Path;
+
+type Layer = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+impl Modifier for Haze {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let density = self.density()?;
+		let airlight = self.airlight()?;
+		let color = image.color();
+
+		let (width, height) = (image.width(), image.height());
+
+		let depth = match self.depth() {
+			Some(directory) => load_depth(directory, image.filename(), width, height)?,
+			None => Layer::from_fn(width, height, |_, y| Luma([1.0 - y as f32 / (height - 1).max(1) as f32])),
+		};
+
+		let mut buffer = image.source().to_rgba32f();
+
+		// Atmospheric scattering model: I = J * t + A * (1 - t) with t = exp(-beta * d)
+		for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+			let transmission = (-density * depth.get_pixel(x, y)[0]).exp();
+
+			for channel in pixel.0[..3].iter_mut() {
+				*channel = *channel * transmission + airlight * (1.0 - transmission);
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "density", density)?;
+		image.record(self.id(), "airlight", airlight)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Rain {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let density = self.density()?;
+		let length = self.length()?;
+		let angle = self.angle()?;
+		let intensity = self.intensity()?;
+		let color = image.color();
+
+		let (width, height) = (image.width(), image.height());
+		let (sin, cos) = angle.to_radians().sin_cos();
+
+		let mut layer = Layer::new(width, height);
+		let mut rng = thread_rng();
+
+		// Streaks start above and beside the image so the borders are covered as well
+		let margin = length * sin.abs();
+		let count = (density * width as f32 * height as f32).round() as u32;
+
+		for _ in 0..count {
+			let x = rng.gen_range(-margin..=width as f32 + margin);
+			let y = rng.gen_range(-length..=height as f32);
+			let streak = length * rng.gen_range(0.5..=1.0);
+			let brightness = intensity * rng.gen_range(0.5..=1.0);
+
+			let steps = (streak * 2.0).ceil() as u32;
+
+			for step in 0..steps {
+				let t = step as f32 / 2.0;
+				splat(&mut layer, x + t * sin, y + t * cos, brightness / 2.0);
+			}
+		}
+
+		let layer = gaussian_blur_f32(&layer, 0.6);
+		let mut buffer = image.source().to_rgba32f();
+
+		// Screen blend, streaks brighten without clipping the background
+		for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+			let streak = layer.get_pixel(x, y)[0].clamp(0.0, 1.0);
+
+			for channel in pixel.0[..3].iter_mut() {
+				*channel = 1.0 - (1.0 - *channel) * (1.0 - streak);
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "density", density)?;
+		image.record(self.id(), "length", length)?;
+		image.record(self.id(), "angle", angle)?;
+		image.record(self.id(), "intensity", intensity)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+impl Modifier for Snow {
+	fn apply(&self, image: &mut Image) -> Result<()> {
+		let density = self.density()?;
+		let size = self.size()?;
+		let length = self.length()?;
+		let angle = self.angle()?;
+		let color = image.color();
+
+		let (width, height) = (image.width(), image.height());
+		let (sin, cos) = angle.to_radians().sin_cos();
+
+		let mut layer = Layer::new(width, height);
+		let mut rng = thread_rng();
+
+		let count = (density * width as f32 * height as f32).round() as u32;
+
+		for _ in 0..count {
+			let x = rng.gen_range(0.0..width as f32);
+			let y = rng.gen_range(0.0..height as f32);
+			let radius = size * rng.gen_range(0.3..=1.0);
+			let opacity = rng.gen_range(0.6..=1.0);
+
+			// Moving flakes are the average of the disc along their path
+			let steps = length.ceil() as u32 + 1;
+
+			for step in 0..steps {
+				let t = step as f32 - length / 2.0;
+				disc(&mut layer, x + t * sin, y + t * cos, radius, opacity / steps as f32);
+			}
+		}
+
+		let mut buffer = image.source().to_rgba32f();
+
+		for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+			let alpha = layer.get_pixel(x, y)[0].clamp(0.0, 1.0);
+
+			for channel in pixel.0[..3].iter_mut() {
+				*channel = *channel * (1.0 - alpha) + alpha;
+			}
+		}
+
+		*image.source_mut() = convert_layout(&ImageRgba32F(buffer), color, BitDepth::Original);
+
+		image.record(self.id(), "density", density)?;
+		image.record(self.id(), "size", size)?;
+		image.record(self.id(), "length", length)?;
+		image.record(self.id(), "angle", angle)?;
+
+		Ok(())
+	}
+	fn id(&self) -> &str {
+		self.id.as_ref()
+	}
+}
+
+/// Loads the depth map with the same file stem as `filename` and fits it to the image.
+fn load_depth(directory: &Path, filename: &str, width: u32, height: u32) -> Result<Layer> {
+	let stem = Path::new(filename).file_stem();
+
+	let path = fs::read_dir(directory)?
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.find(|path| path.is_file() && path.file_stem() == stem)
+		.ok_or(anyhow!("no depth map for {} in {}", filename, directory.display()))?;
+
+	let mut depth = Reader::open(path)?.with_guessed_format()?.decode()?;
+
+	if depth.width() != width || depth.height() != height {
+		depth = resize_image(&depth, width, height, Type::Triangle)?;
+	}
+
+	Ok(depth.to_luma32f())
+}
+
+/// Adds `value` at a fractional position, spread over the four nearest pixels.
+fn splat(layer: &mut Layer, x: f32, y: f32, value: f32) {
+	let (x0, y0) = (x.floor(), y.floor());
+	let (fx, fy) = (x - x0, y - y0);
+
+	let corners = [(0.0, 0.0, (1.0 - fx) * (1.0 - fy)), (1.0, 0.0, fx * (1.0 - fy)), (0.0, 1.0, (1.0 - fx) * fy), (1.0, 1.0, fx * fy)];
+
+	for (dx, dy, weight) in corners {
+		let (px, py) = (x0 + dx, y0 + dy);
+
+		if px >= 0.0 && py >= 0.0 && px < layer.width() as f32 && py < layer.height() as f32 {
+			layer.get_pixel_mut(px as u32, py as u32)[0] += value * weight;
+		}
+	}
+}
+
+/// Adds a disc with an antialiased edge.
+fn disc(layer: &mut Layer, cx: f32, cy: f32, radius: f32, value: f32) {
+	let (width, height) = layer.dimensions();
+
+	let left = (cx - radius - 1.0).floor().max(0.0) as u32;
+	let top = (cy - radius - 1.0).floor().max(0.0) as u32;
+	let right = ((cx + radius + 1.0).ceil().max(0.0) as u32).min(width);
+	let bottom = ((cy + radius + 1.0).ceil().max(0.0) as u32).min(height);
+
+	for y in top..bottom {
+		for x in left..right {
+			let distance = ((x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)).sqrt();
+			let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0);
+
+			layer.get_pixel_mut(x, y)[0] += value * coverage;
+		}
+	}
+}
@@ -1,5 +1,9 @@
 use crate::processor::image::Image;
+use crate::processor::image::Record;
 use crate::config::*;
+use crate::config::enumerations::ColorSpace;
+use crate::config::enumerations::Parameter;
+use crate::config::enumerations::Plane;
 
 use self::image::Modifier;
 
@@ -9,6 +13,9 @@ use anyhow::anyhow;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Serialize;
 
 use rayon::prelude::*;
 use num_traits::AsPrimitive;
@@ -28,6 +35,21 @@ pub enum IdType {
 	Sequence,
 }
 
+pub enum Step {
+	Modifier(String),
+	Planes {
+		ids: Vec<String>,
+		space: ColorSpace,
+		planes: Vec<Plane>,
+	},
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+	file: PathBuf,
+	records: Vec<Record>,
+}
+
 impl Config {
 	pub fn open<P>(path: P) -> Result<Config> where P: AsRef<Path> {
 		let mut file = File::open(path)?;
@@ -35,6 +57,11 @@ impl Config {
 		file.read_to_string(&mut string)?;
 
 		let config: Config = serde_yaml::from_str(&string)?;
+
+		for modifier in config.define().into_iter().flatten() {
+			modifier.validate()?;
+		}
+
 		Ok(config)
 	}
 	pub fn get_modifier<S>(&self, id: S) -> Option<Box<dyn Modifier>> where S: AsRef<str> {
@@ -71,37 +98,79 @@ impl Config {
 		let modifier = self.get_modifier(id);
 		let sequence = self.get_sequence(id);
 
-		if let Some(_) = modifier {
+		if modifier.is_some() {
 			return Ok(IdType::Modifier);
 		}
 
-		if let Some(_) = sequence {
+		if sequence.is_some() {
 			return Ok(IdType::Sequence);
 		}
 
-		return Err(anyhow!("Unknown ID or IdType"));
+		Err(anyhow!("Unknown ID or IdType"))
 	}
-	fn unwrap_id<S>(&self, id: S) -> Result<Vec<String>> where S: AsRef<str> {
+	fn unwrap_id<S>(&self, id: S) -> Result<Vec<Step>> where S: AsRef<str> {
 		let id = id.as_ref();
 
-		let mut unwrapped = Vec::<String>::new();
+		let mut unwrapped = Vec::<Step>::new();
 		let id_type = self.get_type(id)?;
 
 		match id_type {
-			IdType::Modifier => unwrapped.push(id.to_owned()),
+			IdType::Modifier => unwrapped.push(Step::Modifier(id.to_owned())),
 			IdType::Sequence => {
 				let sequence = self.get_sequence(id).unwrap();
 
 				for elem in sequence.elements() {
-					for id in elem.id_seq()? {
-						unwrapped.extend(self.unwrap_id(id)?);
-					}
+					unwrapped.extend(self.unwrap_element(elem)?);
+				}
+			}
+		}
+
+		Ok(unwrapped)
+	}
+	fn unwrap_element(&self, element: &Parameter) -> Result<Vec<Step>> {
+		if let Parameter::Planes { id, space, planes } = element {
+			let mut ids = Vec::new();
+
+			for step in self.unwrap_id(id)? {
+				match step {
+					Step::Modifier(id) => ids.push(id),
+					Step::Planes { .. } => return Err(anyhow!("nested planes are not supported")),
 				}
 			}
+
+			return Ok(vec![Step::Planes { ids, space: *space, planes: planes.clone() }]);
+		}
+
+		let mut unwrapped = Vec::new();
+
+		for id in element.id_seq()? {
+			unwrapped.extend(self.unwrap_id(id)?);
 		}
 
 		Ok(unwrapped)
 	}
+	fn apply_to_planes(&self, image: &mut Image, ids: &[String], space: ColorSpace, selected: &[Plane]) -> Result<()> {
+		let mut planes = image.separate_planes(space)?;
+
+		for plane in selected {
+			let index = plane.index(space)?;
+			let mut channel = planes
+				.get(index)
+				.cloned()
+				.ok_or(anyhow!("image has no {:?} plane", plane))?;
+
+			for id in ids {
+				let modifier = self.get_modifier(id).ok_or(anyhow!("Unknown ID"))?;
+				channel = image.apply_to_plane(modifier.as_ref(), channel)?;
+			}
+
+			planes[index] = channel;
+		}
+
+		*image.source_mut() = image.combine_planes(space, planes)?;
+
+		Ok(())
+	}
 	fn init_progress_bar(&self, len: u64) -> Result<ProgressBar> {
 		let progress_bar = self.progress();
 
@@ -127,31 +196,31 @@ impl Config {
 
 		Ok(progress)
 	}
-	fn process_image<P>(&self, path: P) -> Result<()> where P: AsRef<Path> {
+	fn process_image<P>(&self, path: P) -> Result<ManifestEntry> where P: AsRef<Path> {
 		let output = self.output().ok_or(anyhow!("output"))?;
 		let output_path = output.path();
 
-		let mut image = Image::new(path)?;
-		let mut id_seq = Vec::new();
+		let mut image = Image::new(&path)?;
+		let steps = self.unwrap_element(self.execute().ok_or(anyhow!("execute"))?)?;
 
-		for id in self.execute().ok_or(anyhow!("execute"))?.id_seq()? {
-			id_seq.extend(self.unwrap_id(id)?);
-		}
-
-		for id in id_seq.iter() {
-			let modifier = self.get_modifier(id);
-
-			if let Some(modifier) = modifier {
-				modifier.apply(&mut image)?;
-				continue;
+		for step in steps.iter() {
+			match step {
+				Step::Modifier(id) => {
+					let modifier = self.get_modifier(id).ok_or(anyhow!("Unknown ID"))?;
+					modifier.apply(&mut image)?;
+				}
+				Step::Planes { ids, space, planes } => {
+					self.apply_to_planes(&mut image, ids, *space, planes)?;
+				}
 			}
-
-			return Err(anyhow!("Unknown ID"));
 		}
 
-		image.save(output_path)?;
+		image.save(output_path, output)?;
 
-		Ok(())
+		let file = path.as_ref().to_owned();
+		let records = image.records().clone();
+
+		Ok(ManifestEntry { file, records })
 	}
 	pub fn start_parallel_processing(&self) -> Result<()> {
 		let input = self.input().ok_or(anyhow!("input"))?;
@@ -160,26 +229,39 @@ impl Config {
 		let in_error = AtomicBool::new(false);
 		let progress = self.init_progress_bar(path_vector.len().as_())?;
 
-		let results: Vec<Result<(), anyhow::Error>> = path_vector
+		let results: Vec<Result<Option<ManifestEntry>, anyhow::Error>> = path_vector
 			.par_iter()
 			.map(|path| {
 				if !in_error.load(Ordering::Relaxed) {
 					let result = self.process_image(path);
 
-					if let Err(err) = result {
-						in_error.store(true, Ordering::Relaxed);
-						return Err(err);
-					}
-
-					progress.inc(1);
+					return match result {
+						Ok(entry) => {
+							progress.inc(1);
+							Ok(Some(entry))
+						}
+						Err(err) => {
+							in_error.store(true, Ordering::Relaxed);
+							Err(err)
+						}
+					};
 				}
 
-				Ok(())
+				Ok(None)
 			})
 			.collect();
 
+		let mut manifest = Vec::new();
+
 		for result in results {
-			result?;
+			manifest.extend(result?);
+		}
+
+		let output = self.output().ok_or(anyhow!("output"))?;
+
+		if let Some(path) = output.manifest() {
+			let file = File::create(path)?;
+			serde_yaml::to_writer(file, &manifest)?;
 		}
 
 		Ok(())
@@ -1,8 +1,11 @@
 use crate::config::enumerations::Parameter;
+use crate::config::enumerations::Choice;
+use crate::config::enumerations::Variants;
 use rand::prelude::*;
 use anyhow::Result;
 use anyhow::anyhow;
 use num_traits::AsPrimitive;
+use std::ops::RangeBounds;
 
 impl Parameter {
 	pub fn int(&self) -> Result<u32> {
@@ -16,7 +19,7 @@ impl Parameter {
 			Self::RandIntFrom(i) =>
 				i
 					.choose(&mut rng)
-					.and_then(|i| Some(*i))
+					.copied()
 					.ok_or(anyhow!("int")),
 
 			_ => Err(anyhow!("int")),
@@ -33,12 +36,40 @@ impl Parameter {
 			Self::RandFloatFrom(f) =>
 				f
 					.choose(&mut rng)
-					.and_then(|f| Some(*f))
+					.copied()
 					.ok_or(anyhow!("float")),
 
 			_ => Err(anyhow!("float")),
 		}
 	}
+	/// Whether every value `int` can return lies in `range`.
+	pub fn int_within<R>(&self, range: R) -> Result<bool> where R: RangeBounds<u32> {
+		let (low, high) = match self {
+			Self::Int(i) => (*i, *i),
+			Self::RandInt(s, e) => (s.as_(), e.saturating_sub(1).max(*s).as_()),
+			Self::RandIntFrom(i) => (
+				i.iter().copied().min().ok_or(anyhow!("int"))?,
+				i.iter().copied().max().ok_or(anyhow!("int"))?,
+			),
+			_ => return Err(anyhow!("int")),
+		};
+
+		Ok(range.contains(&low) && range.contains(&high))
+	}
+	/// Whether every value `float` can return lies in `range`, the end of `RandFloat` counts as reachable.
+	pub fn float_within<R>(&self, range: R) -> Result<bool> where R: RangeBounds<f32> {
+		let (low, high) = match self {
+			Self::Float(f) => (*f, *f),
+			Self::RandFloat(s, e) => (*s, *e),
+			Self::RandFloatFrom(f) if !f.is_empty() => (
+				f.iter().copied().fold(f32::INFINITY, f32::min),
+				f.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+			),
+			_ => return Err(anyhow!("float")),
+		};
+
+		Ok(range.contains(&low) && range.contains(&high))
+	}
 	pub fn id_seq(&self) -> Result<Vec<&str>> {
 		let mut rng = thread_rng();
 
@@ -48,7 +79,7 @@ impl Parameter {
 			Self::RandId(i) =>
 				i
 					.choose(&mut rng)
-					.and_then(|s| Some(vec![s.as_ref()]))
+					.map(|s| vec![s.as_ref()])
 					.ok_or(anyhow!("not id")),
 
 			Self::RandMulId(i, amount) =>
@@ -61,3 +92,42 @@ impl Parameter {
 		}
 	}
 }
+
+impl<T> Choice<T> where T: Variants + Clone + PartialEq {
+	pub fn get(&self) -> Result<T> {
+		let mut rng = thread_rng();
+
+		match self {
+			Self::Value(v) => Ok(v.clone()),
+
+			Self::Rand =>
+				T::VARIANTS
+					.choose(&mut rng)
+					.cloned()
+					.ok_or(anyhow!("choice")),
+
+			Self::RandFrom(v) =>
+				v
+					.choose(&mut rng)
+					.cloned()
+					.ok_or(anyhow!("choice")),
+
+			Self::RandWithout(v) =>
+				T::VARIANTS
+					.iter()
+					.filter(|variant| !v.contains(variant))
+					.choose(&mut rng)
+					.cloned()
+					.ok_or(anyhow!("choice")),
+		}
+	}
+	/// Every value `get` can return.
+	pub fn candidates(&self) -> Vec<T> {
+		match self {
+			Self::Value(v) => vec![v.clone()],
+			Self::Rand => T::VARIANTS.to_vec(),
+			Self::RandFrom(v) => v.clone(),
+			Self::RandWithout(v) => T::VARIANTS.iter().filter(|variant| !v.contains(variant)).cloned().collect(),
+		}
+	}
+}